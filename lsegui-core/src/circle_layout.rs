@@ -0,0 +1,578 @@
+use petgraph::stable_graph::NodeIndex;
+
+use crate::GlyphError;
+use crate::NodeLayout::*;
+use crate::Phrase;
+use crate::Point;
+use crate::Word;
+
+//Arc in degrees kept free of nodes around a point where two circles touch without sharing a letter
+const REPEL_GAP: f32 = 60.0;
+//Where a circle touches the next one, in degrees from where it touches the previous one
+const BOTTOM: f32 = 180.0;
+//Touching points tried, in order, when a circle would overlap an earlier one
+const ROTATIONS: [f32; 9] = [BOTTOM, 150.0, 210.0, 120.0, 240.0, 90.0, 270.0, 60.0, 300.0];
+//Circle placements tried before the solver gives up
+const SEARCH_LIMIT: usize = 20_000;
+//Nodes set closer than this to where they already are don't count as moved
+pub(crate) const MOVED_BY: f32 = 0.05;
+
+#[derive(Clone, Debug)]
+pub struct NodePos {
+    pub pos: Point,
+    pub node: NodeIndex<u32>,
+    //Degrees clockwise from the circle's rotation
+    pub angle: f32,
+    //Moved by hand away from where the layout put it
+    pub moved: bool,
+}
+#[derive(Clone, Debug)]
+pub struct NodePosList {
+    pub node_pos_list: Vec<NodePos>,
+}
+#[derive(Clone, Debug)]
+pub struct Circle {
+    pub center: Point,
+    pub radius: f32,
+    pub word: Word,
+    pub node_pos_list: NodePosList,
+    pub origin: Point,
+    pub rotation: f32,
+    //Moved or turned by hand, so a new layout leaves it where it is
+    pub pinned: bool,
+}
+#[derive(Clone, Debug, Default)]
+pub struct CircleLayout {
+    pub circle_list: Vec<Circle>,
+}
+
+impl Circle {
+    pub fn new(word: Word) -> Self {
+        let node_pos = word
+            .nodes
+            .iter()
+            .map(|node| NodePos {
+                pos: Point::new(0.0, 0.0),
+                node: *node,
+                angle: 0.0,
+                moved: false,
+            })
+            .collect::<Vec<NodePos>>();
+        let node_pos_list = NodePosList {
+            node_pos_list: node_pos,
+        };
+        let origin = Point::new(0.0, 0.0);
+        let center = Point::new(0.0, 0.0);
+        let radius = 20.0 * word.word.len() as f32;
+        let rotation = -90.0;
+
+        Circle {
+            center,
+            radius,
+            word,
+            node_pos_list,
+            origin,
+            rotation,
+            pinned: false,
+        }
+    }
+
+    pub fn get_node_pos(&self, node: NodeIndex<u32>) -> Option<Point> {
+        self.node_pos_list
+            .node_pos_list
+            .iter()
+            .find(|node_pos| node_pos.node == node)
+            .map(|node_pos| node_pos.pos)
+    }
+
+    pub fn get_angle(&self, origin: Point) -> f32 {
+        (origin.y - self.center.y)
+            .atan2(origin.x - self.center.x)
+            .to_degrees()
+    }
+
+    /// Degrees clockwise from the rotation of the circle to `pos`, from 0 to 360.
+    pub fn angle_of(&self, pos: Point) -> f32 {
+        let local = self.to_local(pos);
+        local.y.atan2(local.x).to_degrees().rem_euclid(360.0)
+    }
+
+    /// `pos` relative to the center and rotation of the circle.
+    pub fn to_local(&self, pos: Point) -> Point {
+        (pos - self.center).rotated(-self.rotation)
+    }
+
+    /// The inverse of [`Circle::to_local`].
+    pub fn from_local(&self, local: Point) -> Point {
+        self.center + local.rotated(self.rotation)
+    }
+
+    /// Moves and turns the circle together with its letter nodes, and pins
+    /// it there.
+    pub fn set_placement(&mut self, center: Point, rotation: f32) {
+        let origin = self.to_local(self.origin);
+        let nodes = self
+            .node_pos_list
+            .node_pos_list
+            .iter()
+            .map(|node_pos| self.to_local(node_pos.pos))
+            .collect::<Vec<_>>();
+        self.center = center;
+        self.rotation = rotation;
+        self.pinned = true;
+        self.origin = self.from_local(origin);
+        let placed = nodes
+            .into_iter()
+            .map(|local| self.from_local(local))
+            .collect::<Vec<_>>();
+        self.node_pos_list
+            .node_pos_list
+            .iter_mut()
+            .zip(placed)
+            .for_each(|(node_pos, pos)| node_pos.pos = pos);
+    }
+}
+
+impl CircleLayout {
+    pub fn new() -> Self {
+        CircleLayout {
+            circle_list: Vec::new(),
+        }
+    }
+
+    /// Places a circle for every word of `phrase`, each touching the one
+    /// before it, trying other touching points and shared letters until no
+    /// two circles overlap.
+    pub fn layout(&mut self, phrase: &Phrase) -> Result<(), GlyphError> {
+        self.layout_around(phrase, &[])
+    }
+
+    /// Like [`CircleLayout::layout`], keeping clear of the circles pinned for
+    /// some words, given by word index. The circles laid out for those words
+    /// themselves are left for the caller to put in place of.
+    pub fn layout_around(
+        &mut self,
+        phrase: &Phrase,
+        pinned: &[Option<Circle>],
+    ) -> Result<(), GlyphError> {
+        self.circle_list.clear();
+        let mut budget = SEARCH_LIMIT;
+        if self.place(phrase, 0, &mut budget, pinned) {
+            Ok(())
+        } else {
+            Err(GlyphError::NoArrangement)
+        }
+    }
+
+    //Places the circle of word i and then the ones after it, backtracking when they can't fit
+    fn place(
+        &mut self,
+        phrase: &Phrase,
+        i: usize,
+        budget: &mut usize,
+        pinned: &[Option<Circle>],
+    ) -> bool {
+        let Some(word) = phrase.phrase_words.get(i) else {
+            //Nothing touches the bottom of the last circle
+            if let Some(last) = self.circle_list.last_mut() {
+                place_nodes(last, None);
+            }
+            return true;
+        };
+        let Some(previous) = self.circle_list.last().cloned() else {
+            let circle = Circle::new(word.clone());
+            if overlaps_pinned(&circle, i, pinned) {
+                return false;
+            }
+            self.circle_list.push(circle);
+            if self.place(phrase, i + 1, budget, pinned) {
+                return true;
+            }
+            self.circle_list.pop();
+            return false;
+        };
+
+        for (previous_word, word) in anchor_letters(&previous.word, word) {
+            for bottom in touching_points(&previous_word) {
+                if *budget == 0 {
+                    return false;
+                }
+                *budget -= 1;
+
+                let mut prev_circle = Circle {
+                    word: previous_word.clone(),
+                    ..previous.clone()
+                };
+                let angle = prev_circle.rotation + place_nodes(&mut prev_circle, bottom);
+                let mut circle = Circle::new(word.clone());
+                circle.origin = prev_circle.center.polar(angle, prev_circle.radius);
+                circle.center = prev_circle
+                    .center
+                    .polar(angle, prev_circle.radius + circle.radius);
+                circle.rotation = angle + 180.0;
+
+                //The previous circle only touches it, so just the ones before that can overlap
+                if self.circle_overlaps(&circle, i - 1) || overlaps_pinned(&circle, i, pinned) {
+                    continue;
+                }
+                self.circle_list[i - 1] = prev_circle;
+                self.circle_list.push(circle);
+                if self.place(phrase, i + 1, budget, pinned) {
+                    return true;
+                }
+                self.circle_list.pop();
+            }
+        }
+        self.circle_list[i - 1] = previous;
+        false
+    }
+
+    /// Returns the position of every letter node laid out on the circles.
+    pub fn node_positions(&self) -> impl Iterator<Item = (NodeIndex<u32>, Point)> + '_ {
+        self.circle_list.iter().flat_map(|circle| {
+            circle
+                .node_pos_list
+                .node_pos_list
+                .iter()
+                .map(|node_pos| (node_pos.node, node_pos.pos))
+        })
+    }
+
+    /// Moves a node, for example after it was dragged in a view of the glyph.
+    /// The node is marked as moved unless `pos` is where it already is.
+    pub fn set_node_pos(&mut self, node: NodeIndex<u32>, pos: Point) {
+        self.move_node(node, |_| pos);
+    }
+
+    /// Moves a node to the point on the ring of its circle closest to `pos`,
+    /// so it slides along the ring as it is dragged.
+    pub fn slide_node(&mut self, node: NodeIndex<u32>, pos: Point) {
+        self.move_node(node, |circle| {
+            circle.center.polar(circle.get_angle(pos), circle.radius)
+        });
+    }
+
+    //Puts a node where `place` says on its circle, keeping its angle up to date
+    fn move_node(&mut self, node: NodeIndex<u32>, place: impl Fn(&Circle) -> Point) {
+        self.circle_list.iter_mut().for_each(|circle| {
+            let pos = place(circle);
+            let angle = circle.angle_of(pos);
+            circle
+                .node_pos_list
+                .node_pos_list
+                .iter_mut()
+                .filter(|node_pos| node_pos.node == node && node_pos.pos.distance(pos) > MOVED_BY)
+                .for_each(|node_pos| {
+                    node_pos.pos = pos;
+                    node_pos.angle = angle;
+                    node_pos.moved = true;
+                });
+        });
+    }
+
+    fn circle_overlaps(&self, circle: &Circle, before: usize) -> bool {
+        self.circle_list
+            .iter()
+            .take(before)
+            .any(|other| overlaps(other, circle))
+    }
+
+    /// Whether any two circles overlap, as they can once circles are pinned
+    /// where they were dragged to.
+    pub fn has_overlaps(&self) -> bool {
+        self.circle_list
+            .iter()
+            .enumerate()
+            .any(|(i, circle)| self.circle_overlaps(circle, i))
+    }
+}
+
+fn overlaps(a: &Circle, b: &Circle) -> bool {
+    //Leave some slack for circles that only just touch
+    a.center.distance(b.center) + 0.01 < a.radius + b.radius
+}
+
+//Whether the circle for word i is in the way of the circle pinned for another word
+fn overlaps_pinned(circle: &Circle, i: usize, pinned: &[Option<Circle>]) -> bool {
+    pinned
+        .iter()
+        .enumerate()
+        .filter(|(j, _)| *j != i)
+        .filter_map(|(_, other)| other.as_ref())
+        .any(|other| overlaps(other, circle))
+}
+
+//Ways to join two words: the same letter shared at another place in either of them
+fn anchor_letters(previous: &Word, word: &Word) -> Vec<(Word, Word)> {
+    let (SameCharBottom(bottom), SameCharTop(top)) = (previous.layout_bottom, word.layout_top)
+    else {
+        return vec![(previous.clone(), word.clone())];
+    };
+    let shared = previous.word.chars().nth(bottom);
+    //Letters can't be joined to both neighbours, and the boundary letters come first
+    let occurrences = |word: &Word, first: usize, taken: Option<usize>| {
+        let mut indices = word
+            .word
+            .chars()
+            .enumerate()
+            .filter(|(i, c)| Some(*c) == shared && Some(*i) != taken)
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        indices.sort_by_key(|i| *i != first);
+        indices
+    };
+    let previous_top = match previous.layout_top {
+        SameCharTop(i) => Some(i),
+        _ => None,
+    };
+    let word_bottom = match word.layout_bottom {
+        SameCharBottom(i) => Some(i),
+        _ => None,
+    };
+    let word_tops = occurrences(word, top, word_bottom);
+    occurrences(previous, bottom, previous_top)
+        .into_iter()
+        .flat_map(|bottom| {
+            word_tops.iter().map(move |top| {
+                (
+                    Word {
+                        layout_bottom: SameCharBottom(bottom),
+                        ..previous.clone()
+                    },
+                    Word {
+                        layout_top: SameCharTop(*top),
+                        ..word.clone()
+                    },
+                )
+            })
+        })
+        .collect()
+}
+
+//Where the next circle can touch, the first being where a joined letter falls on its own
+fn touching_points(word: &Word) -> Vec<Option<f32>> {
+    let natural = match word.layout_bottom {
+        SameCharBottom(_) => Some(None),
+        _ => None,
+    };
+    natural
+        .into_iter()
+        .chain(ROTATIONS.iter().map(|angle| Some(*angle)))
+        .collect()
+}
+
+//Positions the letters of a circle from its rotation and returns the angle where it touches the next circle
+fn place_nodes(circle: &mut Circle, bottom: Option<f32>) -> f32 {
+    let angles = node_angles(&circle.word, bottom);
+    let touching = match (circle.word.layout_bottom, bottom) {
+        (_, Some(bottom)) => bottom,
+        (SameCharBottom(i), None) => angles.get(i).copied().unwrap_or(BOTTOM),
+        _ => BOTTOM,
+    };
+    let (center, radius, rotation) = (circle.center, circle.radius, circle.rotation);
+    circle
+        .node_pos_list
+        .node_pos_list
+        .iter_mut()
+        .zip(angles)
+        .for_each(|(node_pos, angle)| {
+            node_pos.angle = angle;
+            node_pos.pos = center.polar(rotation + angle, radius);
+        });
+    touching
+}
+
+/// Angles of the letters of `word` in degrees clockwise from where its circle
+/// touches the previous one, given the angle `bottom` where it touches the
+/// next. Without one a joined bottom letter stays where it falls.
+///
+/// Joined letters sit exactly on the touching points, the rest are spread
+/// evenly between them, skipping the gaps left around repelling points.
+fn node_angles(word: &Word, bottom: Option<f32>) -> Vec<f32> {
+    let len = word.word.chars().count();
+    let mut angles = vec![0.0; len];
+    if len == 0 {
+        return angles;
+    }
+
+    let mut fixed = vec![];
+    let mut gaps = vec![];
+    match word.layout_bottom {
+        SameCharBottom(i) => fixed.extend(bottom.map(|bottom| (i, bottom))),
+        RepelBottom => gaps.push(bottom.unwrap_or(BOTTOM)),
+        _ => (),
+    }
+    match word.layout_top {
+        SameCharTop(i) if fixed.iter().all(|(fixed_i, _)| *fixed_i != i) => {
+            fixed.insert(0, (i, 0.0))
+        }
+        //Without a joined letter to anchor them the first and last letters sit at the edges of the gap
+        RepelTop if fixed.is_empty() && len == 1 => {
+            gaps.push(0.0);
+            fixed.push((0, bottom.unwrap_or(BOTTOM) / 2.0));
+        }
+        RepelTop if fixed.is_empty() => {
+            gaps.push(0.0);
+            fixed.push((0, REPEL_GAP / 2.0));
+            fixed.push((len - 1, 360.0 - REPEL_GAP / 2.0));
+        }
+        RepelTop => gaps.push(0.0),
+        _ if fixed.is_empty() => fixed.push((0, 0.0)),
+        _ => (),
+    }
+
+    (0..fixed.len()).for_each(|k| {
+        let (from, start) = fixed[k];
+        let (to, mut end) = fixed[(k + 1) % fixed.len()];
+        if k + 1 == fixed.len() {
+            end += 360.0;
+        }
+        angles[from] = start;
+        //Letters strictly between the two fixed ones, going round the word
+        let between = (to + len - from - 1) % len;
+        let free = free_arc(start, end, &gaps);
+        (0..between).for_each(|m| {
+            let along = free * (m + 1) as f32 / (between + 1) as f32;
+            angles[(from + 1 + m) % len] = skip_gaps(start, end, along, &gaps);
+        });
+    });
+    angles
+}
+
+//The gaps around the given centers that fall within start..end, in order
+fn gaps_within(start: f32, end: f32, gaps: &[f32]) -> Vec<(f32, f32)> {
+    let mut within = gaps
+        .iter()
+        .flat_map(|gap| (-1..=2).map(move |turn| gap + 360.0 * turn as f32))
+        .map(|gap| {
+            (
+                (gap - REPEL_GAP / 2.0).max(start),
+                (gap + REPEL_GAP / 2.0).min(end),
+            )
+        })
+        .filter(|(gap_start, gap_end)| gap_start < gap_end)
+        .collect::<Vec<_>>();
+    within.sort_by(|a, b| a.0.total_cmp(&b.0));
+    within
+}
+
+//Length of start..end not covered by gaps
+fn free_arc(start: f32, end: f32, gaps: &[f32]) -> f32 {
+    gaps_within(start, end, gaps)
+        .iter()
+        .fold(end - start, |free, (gap_start, gap_end)| {
+            free - (gap_end - gap_start)
+        })
+}
+
+//The angle reached by moving `along` degrees from start, not counting gaps
+fn skip_gaps(start: f32, end: f32, along: f32, gaps: &[f32]) -> f32 {
+    let mut angle = start;
+    let mut left = along;
+    for (gap_start, gap_end) in gaps_within(start, end, gaps) {
+        if angle + left <= gap_start {
+            break;
+        }
+        left -= (gap_start - angle).max(0.0);
+        angle = angle.max(gap_end);
+    }
+    angle + left
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Glyph, GlyphError, Point};
+
+    //Every pair of circles, as far apart as they are and as far as they need to be
+    fn assert_no_overlaps(glyph: &Glyph) {
+        let circles = &glyph.circles.circle_list;
+        assert_eq!(circles.len(), glyph.phrase.phrase_words.len());
+        circles.iter().enumerate().for_each(|(i, a)| {
+            circles.iter().skip(i + 1).for_each(|b| {
+                let distance = a.center.distance(b.center);
+                assert!(
+                    distance + 0.01 >= a.radius + b.radius,
+                    "{} and {} overlap",
+                    a.word.word,
+                    b.word.word
+                );
+            });
+        });
+    }
+
+    #[test]
+    fn single_letter() {
+        let glyph = Glyph::new("a").unwrap();
+        assert_no_overlaps(&glyph);
+        let circle = &glyph.circles.circle_list[0];
+        let node = &circle.node_pos_list.node_pos_list[0];
+        assert!((node.pos.distance(circle.center) - circle.radius).abs() < 0.01);
+    }
+
+    #[test]
+    fn repeated_words() {
+        assert_no_overlaps(&Glyph::new("la la la la la la").unwrap());
+        assert_no_overlaps(&Glyph::new("hello hello world hello").unwrap());
+    }
+
+    #[test]
+    fn many_words() {
+        assert_no_overlaps(
+            &Glyph::new(
+                "the quick brown fox jumps over the lazy dog while every other animal watches on",
+            )
+            .unwrap(),
+        );
+    }
+
+    #[test]
+    fn pinned_circles_stay_put() {
+        let rules = Default::default();
+        let mut glyph = Glyph::new("hello world").unwrap();
+        glyph.circles.circle_list[1].set_placement(Point::new(400.0, -250.0), 30.0);
+        let before = glyph.circles.circle_list[1].clone();
+
+        glyph.update("hello there world", &rules).unwrap();
+        let after = &glyph.circles.circle_list[2];
+        assert!(after.pinned);
+        assert_eq!(after.word.word, before.word.word);
+        assert!(after.center.distance(before.center) < 0.01);
+        assert!((after.rotation - before.rotation).abs() < 0.01);
+        after
+            .node_pos_list
+            .node_pos_list
+            .iter()
+            .zip(&before.node_pos_list.node_pos_list)
+            .for_each(|(after, before)| assert!(after.pos.distance(before.pos) < 0.01));
+    }
+
+    #[test]
+    fn new_words_keep_clear_of_pinned_circles() {
+        let rules = Default::default();
+        //Pin a circle right where the new word would otherwise go
+        let unpinned = Glyph::new("hello world there").unwrap();
+        let mut glyph = Glyph::new("hello world").unwrap();
+        let spot = unpinned.circles.circle_list[2].center;
+        glyph.circles.circle_list[1].set_placement(spot, 0.0);
+        assert!(!glyph.circles.has_overlaps());
+
+        glyph.update("hello world there", &rules).unwrap();
+        assert!(glyph.circles.circle_list[1].center.distance(spot) < 0.01);
+        assert_no_overlaps(&glyph);
+    }
+
+    #[test]
+    fn overlapping_pinned_circles_are_refused() {
+        let rules = Default::default();
+        let mut glyph = Glyph::new("hello world").unwrap();
+        let center = glyph.circles.circle_list[0].center;
+        glyph.circles.circle_list[1].set_placement(center + Point::new(5.0, 0.0), 0.0);
+
+        assert_eq!(
+            glyph.update("hello world there", &rules).unwrap_err(),
+            GlyphError::NoArrangement
+        );
+        assert_eq!(glyph.phrase.phrase_words.len(), 2);
+        assert!(glyph.circles.circle_list[1].pinned);
+    }
+}
@@ -0,0 +1,417 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use petgraph::stable_graph::{NodeIndex, StableGraph};
+
+use crate::{Phrase, Word};
+
+/// The connection table bundled with LSegui.
+pub const DEFAULT_RULES: &str = include_str!("../rules/default.toml");
+
+/// Which letters every letter connects to in the Gall art form.
+///
+/// Connections are directed: "B" connecting to "A" gives an edge from every
+/// B node to every A node in the same word.
+///
+/// Tables are written as a flat TOML table, one letter per line:
+///
+/// ```toml
+/// [connections]
+/// A = ""
+/// B = "A"
+/// C = "AB"
+/// ```
+///
+/// An optional `reach = 1` line before the table also connects letters to
+/// the letters of the words up to that many words away.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConnectionTable {
+    connections: BTreeMap<char, String>,
+    reach: usize,
+}
+
+/// What an edge of the connection graph joins.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EdgeKind {
+    /// Two letters of the same word.
+    #[default]
+    Within,
+    /// Letters of different words, see [`ConnectionTable::reach`].
+    Between,
+}
+
+/// A problem found on one line of a connection table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuleError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for RuleError {}
+
+impl Default for ConnectionTable {
+    fn default() -> Self {
+        ConnectionTable::parse(DEFAULT_RULES).expect("bundled connection table should be valid")
+    }
+}
+
+impl ConnectionTable {
+    /// Parses a connection table, collecting every error with its line number.
+    pub fn parse(source: &str) -> Result<Self, Vec<RuleError>> {
+        let mut connections = BTreeMap::new();
+        let mut lines = BTreeMap::new();
+        let mut errors = vec![];
+        let mut reach = 0;
+
+        source.lines().enumerate().for_each(|(i, line)| {
+            let line_number = i + 1;
+            let line = strip_comment(line).trim();
+            if line.is_empty() || line == "[connections]" {
+                return;
+            }
+            if let Some((_, value)) = line
+                .split_once('=')
+                .filter(|(key, _)| key.trim() == "reach")
+            {
+                match value.trim().parse::<usize>() {
+                    Ok(value) => reach = value,
+                    Err(_) => errors.push(RuleError {
+                        line: line_number,
+                        message: format!(
+                            "reach must be a number of words, found `{}`",
+                            value.trim()
+                        ),
+                    }),
+                }
+                return;
+            }
+            match parse_entry(line) {
+                Ok((letter, targets)) => {
+                    if let Some(first) = lines.get(&letter) {
+                        errors.push(RuleError {
+                            line: line_number,
+                            message: format!("{letter} is already defined on line {first}"),
+                        });
+                    } else {
+                        lines.insert(letter, line_number);
+                        connections.insert(letter, targets);
+                    }
+                }
+                Err(message) => errors.push(RuleError {
+                    line: line_number,
+                    message,
+                }),
+            }
+        });
+
+        //Every letter that is connected to has to be defined in the table as well
+        connections.iter().for_each(|(letter, targets)| {
+            targets
+                .chars()
+                .filter(|target| !connections.contains_key(target))
+                .for_each(|target| {
+                    errors.push(RuleError {
+                        line: lines[letter],
+                        message: format!("{letter} connects to {target}, which is not defined"),
+                    });
+                });
+        });
+
+        if errors.is_empty() {
+            Ok(ConnectionTable { connections, reach })
+        } else {
+            errors.sort_by_key(|error| error.line);
+            Err(errors)
+        }
+    }
+
+    /// Returns the letters that `letter` connects to.
+    pub fn letter_connections(&self, letter: char) -> &str {
+        self.connections
+            .get(&letter.to_ascii_uppercase())
+            .map_or("", String::as_str)
+    }
+
+    /// How many words to either side the letters of a word connect into.
+    /// At 0 letters only connect within their own word.
+    pub fn reach(&self) -> usize {
+        self.reach
+    }
+
+    pub fn set_reach(&mut self, reach: usize) {
+        self.reach = reach;
+    }
+
+    /// Every letter in the table with the letters it connects to.
+    pub fn letters(&self) -> impl Iterator<Item = (char, &str)> + '_ {
+        self.connections
+            .iter()
+            .map(|(letter, targets)| (*letter, targets.as_str()))
+    }
+
+    /// Writes the table back out in the format read by [`ConnectionTable::parse`].
+    pub fn to_toml(&self) -> String {
+        let mut toml = match self.reach {
+            0 => String::new(),
+            reach => format!("reach = {reach}\n\n"),
+        };
+        toml.push_str("[connections]\n");
+        self.connections.iter().for_each(|(letter, targets)| {
+            toml.push_str(&format!("{letter} = \"{targets}\"\n"));
+        });
+        toml
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    //A '#' only starts a comment outside of a quoted string
+    let mut quote = None;
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, '#') => return &line[..i],
+            _ => (),
+        }
+    }
+    line
+}
+
+fn parse_entry(line: &str) -> Result<(char, String), String> {
+    let (key, value) = line
+        .split_once('=')
+        .ok_or_else(|| format!("expected `LETTER = \"LETTERS\"`, found `{line}`"))?;
+
+    let key = key.trim().trim_matches(|c| c == '"' || c == '\'');
+    let mut key_chars = key.chars();
+    let letter = match (key_chars.next(), key_chars.next()) {
+        (Some(letter), None) if letter.is_ascii_alphabetic() => letter.to_ascii_uppercase(),
+        _ => return Err(format!("`{key}` is not a single letter")),
+    };
+
+    let value = value.trim();
+    let targets = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        .ok_or_else(|| format!("the connections of {letter} must be a quoted string"))?;
+
+    if let Some(bad) = targets.chars().find(|c| !c.is_ascii_alphabetic()) {
+        return Err(format!(
+            "{letter} connects to `{bad}`, which is not a letter"
+        ));
+    }
+
+    Ok((letter, targets.to_ascii_uppercase()))
+}
+
+/// Adds an edge to `phrase.graph` for every letter connection within each word,
+/// and between words as far apart as the reach of `rules`.
+///
+/// Nodes left without any connection in their word are joined to the node of
+/// the closest letter on the alphabet so that no letter floats on its own.
+pub fn build_connections(phrase: &mut Phrase, rules: &ConnectionTable) {
+    phrase
+        .phrase_words
+        .iter()
+        .for_each(|word| connect_word(&mut phrase.graph, word, rules));
+    connect_between(&mut phrase.graph, &phrase.phrase_words, rules);
+}
+
+//Adds the edges of a single word, see build_connections
+pub(crate) fn connect_word(
+    graph: &mut StableGraph<(), EdgeKind>,
+    word: &Word,
+    rules: &ConnectionTable,
+) {
+    let word_char_pairs = word.word.chars().zip(word.nodes.clone());
+
+    word_char_pairs
+        .clone()
+        .for_each(|(current_char, current_node)| {
+            refactor_connections_check(
+                rules.letter_connections(current_char),
+                &word_char_pairs,
+                graph,
+                &current_node,
+            );
+        });
+
+    word.nodes.iter().for_each(|current_node| {
+        if graph
+            .neighbors_directed(*current_node, petgraph::Direction::Outgoing)
+            .count()
+            == 0
+            && graph
+                .neighbors_directed(*current_node, petgraph::Direction::Incoming)
+                .count()
+                == 0
+        {
+            //Out of the nodes in the current word,
+            //connect the current node to the node
+            //representing the character closest to the current character on the alphabet
+            let mut closest_index = 0;
+            let mut closest_distance = 26;
+            let current_char = word_char_pairs
+                .clone()
+                .filter(|(_, n)| *n == *current_node)
+                .map(|(c, _)| c)
+                .next()
+                .unwrap();
+            //Counted before the letters like it are skipped, so the index is a position in the word
+            word_char_pairs
+                .clone()
+                .enumerate()
+                .filter(|(_, (c, _))| *c != current_char)
+                .for_each(|(i, (c, _))| {
+                    let distance = (current_char as i32 - c as i32).abs();
+                    if distance < closest_distance {
+                        closest_distance = distance;
+                        closest_index = i;
+                    }
+                });
+            graph.add_edge(*current_node, word.nodes[closest_index], EdgeKind::Within);
+        }
+    });
+}
+
+//Adds the edges from the letters of every word into the words within reach
+pub(crate) fn connect_between(
+    graph: &mut StableGraph<(), EdgeKind>,
+    words: &[Word],
+    rules: &ConnectionTable,
+) {
+    words.iter().enumerate().for_each(|(i, word)| {
+        words
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i && i.abs_diff(*j) <= rules.reach())
+            .for_each(|(_, other)| {
+                word.word
+                    .chars()
+                    .zip(&word.nodes)
+                    .for_each(|(letter, node)| {
+                        rules.letter_connections(letter).chars().for_each(|target| {
+                            other
+                                .word
+                                .chars()
+                                .zip(&other.nodes)
+                                .filter(|(other_letter, _)| *other_letter == target)
+                                .for_each(|(_, other_node)| {
+                                    graph.add_edge(*node, *other_node, EdgeKind::Between);
+                                });
+                        });
+                    });
+            });
+    });
+}
+
+//Check if the char in the phrase is connected to any other char in the phrase and add an edge between them
+//Some nodes may not get any connections if the current word does not have any letters for the current character to connect to
+fn refactor_connections_check(
+    connections: &str,
+    word_char_pairs: &std::iter::Zip<std::str::Chars<'_>, std::vec::IntoIter<NodeIndex>>,
+    g: &mut StableGraph<(), EdgeKind>,
+    current_node: &NodeIndex,
+) {
+    connections.chars().for_each(|target_char| {
+        word_char_pairs
+            .clone()
+            .filter(|(c_c, _)| *c_c == target_char)
+            .for_each(|target_index| {
+                g.add_edge(*current_node, target_index.1, EdgeKind::Within);
+            });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(source: &str) -> Vec<(usize, String)> {
+        ConnectionTable::parse(source)
+            .unwrap_err()
+            .into_iter()
+            .map(|error| (error.line, error.message))
+            .collect()
+    }
+
+    #[test]
+    fn bundled_table_round_trips() {
+        let rules = ConnectionTable::default();
+        assert_eq!(rules.letters().count(), 26);
+        assert_eq!(rules.letter_connections('c'), "AB");
+        assert_eq!(ConnectionTable::parse(&rules.to_toml()), Ok(rules.clone()));
+
+        let mut reaching = rules;
+        reaching.set_reach(2);
+        assert_eq!(ConnectionTable::parse(&reaching.to_toml()), Ok(reaching));
+    }
+
+    #[test]
+    fn comments_and_quotes() {
+        let rules =
+            ConnectionTable::parse("# Gall\n[connections]\n'A' = '' # nothing\nb = \"a\"\n\n")
+                .unwrap();
+        assert_eq!(rules.letter_connections('A'), "");
+        assert_eq!(rules.letter_connections('B'), "A");
+        //A '#' inside quotes is part of the value, not a comment
+        assert_eq!(
+            errors("A = \"#\""),
+            vec![(1, String::from("A connects to `#`, which is not a letter"))]
+        );
+    }
+
+    #[test]
+    fn every_malformed_line_is_reported() {
+        let source = [
+            "[connections]",
+            "A = \"\"",
+            "B",
+            "AB = \"A\"",
+            "C = A",
+            "D = \"A1\"",
+            "A = \"B\"",
+            "E = \"Q\"",
+            "reach = far",
+            "F = \"A",
+        ]
+        .join("\n");
+        let lines = errors(&source)
+            .into_iter()
+            .map(|(line, _)| line)
+            .collect::<Vec<_>>();
+        assert_eq!(lines, [3, 4, 5, 6, 7, 8, 9, 10]);
+        assert_eq!(
+            errors("A = \"B\"\nB = \"A\"\nA = \"\""),
+            vec![(3, String::from("A is already defined on line 1"))]
+        );
+        assert_eq!(
+            errors("E = \"Q\""),
+            vec![(1, String::from("E connects to Q, which is not defined"))]
+        );
+    }
+
+    #[test]
+    fn lone_letters_join_the_closest_letter() {
+        //Nothing connects to Z and it only connects to A and Y, so it falls back to an O
+        let mut graph = StableGraph::new();
+        let nodes = (0..3).map(|_| graph.add_node(())).collect::<Vec<_>>();
+        let mut phrase = Phrase {
+            phrase_words: vec![Word::new(String::from("ZOO"), nodes.clone())],
+            graph,
+        };
+        build_connections(&mut phrase, &ConnectionTable::default());
+        let targets = phrase
+            .graph
+            .neighbors_directed(nodes[0], petgraph::Direction::Outgoing)
+            .collect::<Vec<_>>();
+        assert_eq!(targets, [nodes[1]]);
+    }
+}
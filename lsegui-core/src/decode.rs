@@ -0,0 +1,359 @@
+use std::collections::HashMap;
+
+use petgraph::stable_graph::{NodeIndex, StableGraph};
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
+
+use crate::{build_connections, ConnectionTable, EdgeKind, Phrase, Word};
+
+//How often each letter turns up in English text, in percent
+const LETTER_FREQUENCY: [(char, f32); 26] = [
+    ('A', 8.2),
+    ('B', 1.5),
+    ('C', 2.8),
+    ('D', 4.3),
+    ('E', 12.7),
+    ('F', 2.2),
+    ('G', 2.0),
+    ('H', 6.1),
+    ('I', 7.0),
+    ('J', 0.15),
+    ('K', 0.77),
+    ('L', 4.0),
+    ('M', 2.4),
+    ('N', 6.7),
+    ('O', 7.5),
+    ('P', 1.9),
+    ('Q', 0.095),
+    ('R', 6.0),
+    ('S', 6.3),
+    ('T', 9.1),
+    ('U', 2.8),
+    ('V', 0.98),
+    ('W', 2.4),
+    ('X', 0.15),
+    ('Y', 2.0),
+    ('Z', 0.074),
+];
+//Readings of one word kept before the search stops looking for more
+const MAX_READINGS: usize = 5_000;
+//Pairs of letters checked per word before the search gives up on finding
+//every reading, which keeps long words down to a fraction of a second
+const SEARCH_LIMIT: usize = 50_000_000;
+//How many pairs a full rebuild of a word counts as, for every pair of its letters
+const REBUILD_PAIRS: usize = 20;
+
+/// Every way one word circle of a glyph can be read.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DecodedWord {
+    /// Spellings that give exactly the connections drawn in the circle, most
+    /// likely first, with their likelihood out of 1.
+    pub readings: Vec<(String, f32)>,
+    /// For every node of the circle, the letters it can be and how likely
+    /// each is, most likely first.
+    pub letters: Vec<Vec<(char, f32)>>,
+    /// The search stopped before finding every reading.
+    pub truncated: bool,
+}
+
+impl DecodedWord {
+    /// The most likely spelling, if the circle can be read at all.
+    pub fn best(&self) -> Option<&str> {
+        self.readings.first().map(|(word, _)| word.as_str())
+    }
+
+    /// More than one spelling gives the same connections.
+    pub fn is_ambiguous(&self) -> bool {
+        self.readings.len() > 1 || self.truncated
+    }
+
+    /// Indices of the nodes whose letter can't be told for certain.
+    pub fn ambiguous_nodes(&self) -> impl Iterator<Item = usize> + '_ {
+        self.letters
+            .iter()
+            .enumerate()
+            .filter(|(_, letters)| letters.len() > 1)
+            .map(|(i, _)| i)
+    }
+}
+
+/// Works out which letters the unlabelled nodes of a glyph stand for.
+///
+/// `words` lists the nodes of every word circle in letter order, as in
+/// [`Word::nodes`]. Only the edges between nodes of the same word are used,
+/// so every word is read on its own and the edges a table with a
+/// [`reach`](ConnectionTable::reach) draws between words are left out. Letters are
+/// weighed by how common they are in English, so the first reading of each
+/// word is the most likely one.
+///
+/// A word whose first letter repeats at the end is drawn without the last
+/// letter, so it also reads back without it.
+pub fn decode(
+    graph: &StableGraph<(), EdgeKind>,
+    words: &[Vec<NodeIndex<u32>>],
+    rules: &ConnectionTable,
+) -> Vec<DecodedWord> {
+    words
+        .iter()
+        .map(|nodes| decode_word(&word_edges(graph, nodes), nodes.len(), rules))
+        .collect()
+}
+
+/// Whether `word` would be drawn with exactly the connections between `nodes`.
+pub fn reads_as(
+    word: &str,
+    graph: &StableGraph<(), EdgeKind>,
+    nodes: &[NodeIndex<u32>],
+    rules: &ConnectionTable,
+) -> bool {
+    let word = word.to_ascii_uppercase();
+    word.chars().count() == nodes.len() && connections_of(&word, rules) == word_edges(graph, nodes)
+}
+
+//Edge counts between the letters of a word, by letter position
+type Edges = HashMap<(usize, usize), usize>;
+
+fn word_edges(graph: &StableGraph<(), EdgeKind>, nodes: &[NodeIndex<u32>]) -> Edges {
+    let position = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (*node, i))
+        .collect::<HashMap<_, _>>();
+    let mut edges = Edges::new();
+    graph.edge_references().for_each(|edge| {
+        if let (Some(source), Some(target)) =
+            (position.get(&edge.source()), position.get(&edge.target()))
+        {
+            *edges.entry((*source, *target)).or_insert(0) += 1;
+        }
+    });
+    edges
+}
+
+//The edges the glyph of a single word is drawn with
+fn connections_of(word: &str, rules: &ConnectionTable) -> Edges {
+    let mut graph = StableGraph::new();
+    let nodes = word.chars().map(|_| graph.add_node(())).collect::<Vec<_>>();
+    let mut phrase = Phrase {
+        phrase_words: vec![Word::new(word.to_string(), nodes.clone())],
+        graph,
+    };
+    build_connections(&mut phrase, rules);
+    word_edges(&phrase.graph, &nodes)
+}
+
+fn decode_word(edges: &Edges, len: usize, rules: &ConnectionTable) -> DecodedWord {
+    //How many edges every letter draws to every other, looked up on every step
+    let mut table = [[0; 26]; 26];
+    ('A'..='Z').zip(table.iter_mut()).for_each(|(letter, row)| {
+        rules
+            .letter_connections(letter)
+            .chars()
+            .filter(char::is_ascii_uppercase)
+            .for_each(|target| row[target as usize - 'A' as usize] += 1);
+    });
+    let mut drawn = vec![0; len * len];
+    edges
+        .iter()
+        .for_each(|((source, target), count)| drawn[source * len + target] = *count);
+    let mut search = Search {
+        edges,
+        rules,
+        table,
+        drawn,
+        letters: Vec::with_capacity(len),
+        readings: vec![],
+        budget: SEARCH_LIMIT,
+        len,
+    };
+    let finished = search.assign();
+
+    let mut readings = search
+        .readings
+        .into_iter()
+        .map(|word| {
+            let weight = word.chars().map(frequency).product::<f32>();
+            (word, weight)
+        })
+        .collect::<Vec<_>>();
+    let total = readings.iter().map(|(_, weight)| weight).sum::<f32>();
+    readings.iter_mut().for_each(|(_, weight)| *weight /= total);
+    readings.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    let letters = (0..len)
+        .map(|i| {
+            let mut letters = HashMap::<char, f32>::new();
+            readings.iter().for_each(|(word, weight)| {
+                if let Some(letter) = word.chars().nth(i) {
+                    *letters.entry(letter).or_insert(0.0) += weight;
+                }
+            });
+            let mut letters = letters.into_iter().collect::<Vec<_>>();
+            letters.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            letters
+        })
+        .collect();
+
+    DecodedWord {
+        readings,
+        letters,
+        truncated: !finished,
+    }
+}
+
+fn frequency(letter: char) -> f32 {
+    LETTER_FREQUENCY
+        .iter()
+        .find(|(known, _)| *known == letter)
+        .map_or(0.01, |(_, frequency)| *frequency / 100.0)
+}
+
+//Backtracking over the letters of one word, one position at a time
+struct Search<'a> {
+    edges: &'a Edges,
+    rules: &'a ConnectionTable,
+    table: [[usize; 26]; 26],
+    //The edges again, by source then target position
+    drawn: Vec<usize>,
+    letters: Vec<char>,
+    readings: Vec<String>,
+    budget: usize,
+    len: usize,
+}
+
+impl Search<'_> {
+    //Returns false when the search had to stop early
+    fn assign(&mut self) -> bool {
+        if self.letters.len() == self.len {
+            let pairs = REBUILD_PAIRS * self.len * self.len;
+            if self.budget < pairs {
+                return false;
+            }
+            self.budget -= pairs;
+            let word = self.letters.iter().collect::<String>();
+            //The fallback edges depend on the whole word, so only a full rebuild can confirm it
+            if connections_of(&word, self.rules) == *self.edges {
+                self.readings.push(word);
+            }
+            return self.readings.len() < MAX_READINGS;
+        }
+        //Every node is checked against every other one for each letter tried
+        let pairs = (self.letters.len() + 1).pow(2);
+        for letter in 'A'..='Z' {
+            if self.budget < pairs {
+                return false;
+            }
+            self.budget -= pairs;
+            self.letters.push(letter);
+            if self.consistent() && !self.assign() {
+                return false;
+            }
+            self.letters.pop();
+        }
+        true
+    }
+
+    //Whether the newest letter can still give the drawn edges
+    fn consistent(&self) -> bool {
+        (0..self.letters.len()).all(|node| self.node_fits(node))
+    }
+
+    //Every node that isn't connected by the table gets at most one extra edge to the closest letter
+    fn node_fits(&self, node: usize) -> bool {
+        let mut extra = 0;
+        let mut connected = false;
+        for other in 0..self.letters.len() {
+            let expected = self.expected(node, other);
+            let drawn = self.drawn(node, other);
+            if drawn < expected {
+                return false;
+            }
+            extra += drawn - expected;
+            connected |= expected > 0 || self.expected(other, node) > 0;
+        }
+        extra <= usize::from(!connected)
+    }
+
+    fn expected(&self, source: usize, target: usize) -> usize {
+        let index = |node: usize| self.letters[node] as usize - 'A' as usize;
+        self.table[index(source)][index(target)]
+    }
+
+    fn drawn(&self, source: usize, target: usize) -> usize {
+        self.drawn[source * self.len + target]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Glyph;
+
+    fn decode_phrase(phrase: &str, rules: &ConnectionTable) -> Vec<DecodedWord> {
+        let glyph = Glyph::with_rules(phrase, rules).unwrap();
+        let words = glyph
+            .phrase
+            .phrase_words
+            .iter()
+            .map(|word| word.nodes.clone())
+            .collect::<Vec<_>>();
+        decode(glyph.graph(), &words, rules)
+    }
+
+    #[test]
+    fn a_word_connecting_every_letter_reads_one_way() {
+        let word = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+        let decoded = decode_phrase(word, &ConnectionTable::default()).remove(0);
+        assert_eq!(decoded.best(), Some(word));
+        assert_eq!(decoded.readings.len(), 1);
+        assert!(!decoded.is_ambiguous());
+        assert_eq!(decoded.ambiguous_nodes().count(), 0);
+    }
+
+    #[test]
+    fn known_words_are_among_their_readings() {
+        let rules = ConnectionTable::default();
+        let decoded = decode_phrase("hello world brought", &rules);
+        ["HELLO", "WORLD", "BROUGHT"]
+            .iter()
+            .zip(&decoded)
+            .for_each(|(word, decoded)| {
+                assert!(!decoded.truncated, "{word}");
+                assert!(
+                    decoded.readings.iter().any(|(reading, _)| reading == word),
+                    "{word}"
+                );
+                //Each of these can be spelled several ways with the same connections
+                assert!(decoded.is_ambiguous(), "{word}");
+                word.chars().zip(&decoded.letters).enumerate().for_each(
+                    |(i, (letter, letters))| {
+                        assert!(letters.iter().any(|(known, _)| *known == letter));
+                        assert_eq!(
+                            decoded.ambiguous_nodes().any(|node| node == i),
+                            letters.len() > 1
+                        );
+                    },
+                );
+            });
+    }
+
+    #[test]
+    fn edges_between_words_are_left_out() {
+        let mut rules = ConnectionTable::default();
+        rules.set_reach(1);
+        let decoded = decode_phrase("hello world", &rules);
+        ["HELLO", "WORLD"]
+            .iter()
+            .zip(&decoded)
+            .for_each(|(word, decoded)| {
+                assert!(decoded.readings.iter().any(|(reading, _)| reading == word));
+            });
+    }
+
+    #[test]
+    fn long_searches_stop_and_are_ambiguous() {
+        let word = "B".repeat(40);
+        let decoded = decode_phrase(&word, &ConnectionTable::default()).remove(0);
+        assert!(decoded.truncated);
+        assert!(decoded.is_ambiguous());
+    }
+}
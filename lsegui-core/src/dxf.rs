@@ -0,0 +1,363 @@
+use std::fmt::Write;
+
+use crate::number::num;
+use crate::{EdgeKind, EdgePath, EdgeStroke, Point, Ring, Scene};
+
+//Decimals of a millimetre coordinates are written with, for stencils as well
+pub(crate) const DECIMALS: usize = 4;
+
+/// Size and cutting widths of a glyph written as DXF or as a stencil, in
+/// millimetres.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DxfOptions {
+    /// How wide the whole glyph comes out.
+    pub width: f32,
+    /// How wide the slot cut for every edge is, and for every ring of a stencil.
+    pub edge_width: f32,
+    /// How much material the cutter burns away along its path. Outlines are
+    /// moved inwards by half of it so holes come out at their drawn size.
+    pub kerf: f32,
+    /// How far the straight steps of an edge outline may stray from the curve.
+    pub tolerance: f32,
+    /// How much material a stencil bridge leaves across a slot.
+    pub bridge_width: f32,
+    /// How many bridges go along every stretch of a stencil slot that needs them.
+    pub bridges: usize,
+}
+
+impl Default for DxfOptions {
+    fn default() -> Self {
+        DxfOptions {
+            width: 200.0,
+            edge_width: 1.5,
+            kerf: 0.2,
+            tolerance: 0.05,
+            bridge_width: 2.0,
+            bridges: 1,
+        }
+    }
+}
+
+/// Writes a scene as an R12 DXF drawing for laser cutters and CAD.
+///
+/// Every word gets a layer of its own with its ring and node dots as circles
+/// and its edges as closed outlines. Edges between words go on a layer of
+/// their own. Units are millimetres with y going up.
+pub fn to_dxf(scene: &Scene, options: &DxfOptions) -> Result<String, String> {
+    let sheet = Sheet::new(scene, options)?;
+    let cuts = scene
+        .rings
+        .iter()
+        .map(|ring| {
+            let circle = Cut::Circle(sheet.place(ring.center), ring.radius * sheet.scale);
+            (ring.word, circle)
+        })
+        .chain(dot_holes(scene, &sheet, options))
+        .chain(scene.edges.iter().filter_map(|edge| {
+            let outline = outline(&sheet.edge_line(&edge.path), sheet.half_width)?;
+            Some((edge_layer(scene, edge), Cut::Outline(outline)))
+        }))
+        .collect::<Vec<_>>();
+    Ok(write_dxf(&layers(scene), &cuts))
+}
+
+/// Where a scene lands on the sheet it is cut from.
+pub(crate) struct Sheet {
+    //The top left corner of the scene, which goes to the bottom left of the sheet once flipped
+    min_x: f32,
+    max_y: f32,
+    /// Millimetres in a unit of the scene.
+    pub(crate) scale: f32,
+    pub(crate) width: f32,
+    pub(crate) height: f32,
+    /// How far edge outlines reach to either side of their line.
+    pub(crate) half_width: f32,
+    tolerance: f32,
+}
+
+impl Sheet {
+    pub(crate) fn new(scene: &Scene, options: &DxfOptions) -> Result<Self, String> {
+        let half_width = (options.edge_width - options.kerf) / 2.0;
+        if options.width <= 0.0 {
+            return Err(String::from("the glyph has to be wider than 0 mm"));
+        }
+        if half_width <= 0.0 {
+            return Err(format!(
+                "a {} mm kerf leaves nothing of {} mm wide edges",
+                options.kerf, options.edge_width
+            ));
+        }
+
+        let bounds = scene.bounds;
+        let scale = options.width / bounds.width();
+        Ok(Sheet {
+            min_x: bounds.min.x,
+            max_y: bounds.max.y,
+            scale,
+            width: options.width,
+            height: bounds.height() * scale,
+            half_width,
+            tolerance: options.tolerance / scale,
+        })
+    }
+
+    /// A point of the scene in millimetres from the bottom left of the sheet.
+    pub(crate) fn place(&self, point: Point) -> Point {
+        Point::new(
+            (point.x - self.min_x) * self.scale,
+            (self.max_y - point.y) * self.scale,
+        )
+    }
+
+    pub(crate) fn edge_line(&self, path: &EdgePath) -> Vec<Point> {
+        path.flatten(self.tolerance)
+            .into_iter()
+            .map(|point| self.place(point))
+            .collect()
+    }
+
+    pub(crate) fn ring_line(&self, ring: &Ring) -> Vec<Point> {
+        ring.flatten(self.tolerance)
+            .into_iter()
+            .map(|point| self.place(point))
+            .collect()
+    }
+}
+
+/// A shape cut out of the sheet.
+pub(crate) enum Cut {
+    Circle(Point, f32),
+    Outline(Vec<Point>),
+}
+
+/// The node dots as holes on the layers of their words.
+pub(crate) fn dot_holes<'a>(
+    scene: &'a Scene,
+    sheet: &'a Sheet,
+    options: &DxfOptions,
+) -> impl Iterator<Item = (usize, Cut)> + 'a {
+    //Dots are holes, so they shrink by the kerf as well
+    let kerf = options.kerf;
+    scene.nodes.iter().filter_map(move |node| {
+        let radius = node.radius * sheet.scale - kerf / 2.0;
+        (radius > 0.0).then(|| (node.word, Cut::Circle(sheet.place(node.center), radius)))
+    })
+}
+
+/// The layer an edge goes on, with edges between words on the last one.
+pub(crate) fn edge_layer(scene: &Scene, edge: &EdgeStroke) -> usize {
+    match edge.kind {
+        EdgeKind::Within => edge.word,
+        EdgeKind::Between => scene.rings.len(),
+    }
+}
+
+/// The names of a layer for every word and one for the edges between them.
+pub(crate) fn layers(scene: &Scene) -> Vec<String> {
+    scene
+        .rings
+        .iter()
+        .map(|ring| word_layer(ring.word, scene))
+        .chain([String::from("BETWEEN")])
+        .collect()
+}
+
+/// Writes cuts, each on the layer of its index, as a DXF drawing.
+pub(crate) fn write_dxf(layers: &[String], cuts: &[(usize, Cut)]) -> String {
+    let mut dxf = String::new();
+    pair(&mut dxf, 0, "SECTION");
+    pair(&mut dxf, 2, "HEADER");
+    pair(&mut dxf, 9, "$ACADVER");
+    pair(&mut dxf, 1, "AC1009");
+    //Metric, as R12 has no $INSUNITS to say millimetres with
+    pair(&mut dxf, 9, "$MEASUREMENT");
+    pair(&mut dxf, 70, 1);
+    pair(&mut dxf, 0, "ENDSEC");
+
+    pair(&mut dxf, 0, "SECTION");
+    pair(&mut dxf, 2, "TABLES");
+    pair(&mut dxf, 0, "TABLE");
+    pair(&mut dxf, 2, "LAYER");
+    pair(&mut dxf, 70, layers.len());
+    layers.iter().enumerate().for_each(|(i, layer)| {
+        pair(&mut dxf, 0, "LAYER");
+        pair(&mut dxf, 2, layer);
+        pair(&mut dxf, 70, 0);
+        //Cycles through the first six colours of the AutoCAD colour index
+        pair(&mut dxf, 62, i % 6 + 1);
+        pair(&mut dxf, 6, "CONTINUOUS");
+    });
+    pair(&mut dxf, 0, "ENDTAB");
+    pair(&mut dxf, 0, "ENDSEC");
+
+    pair(&mut dxf, 0, "SECTION");
+    pair(&mut dxf, 2, "ENTITIES");
+    cuts.iter().for_each(|(layer, cut)| match cut {
+        Cut::Circle(center, radius) => circle(&mut dxf, &layers[*layer], *center, *radius),
+        Cut::Outline(points) => polyline(&mut dxf, &layers[*layer], points),
+    });
+    pair(&mut dxf, 0, "ENDSEC");
+    pair(&mut dxf, 0, "EOF");
+    dxf
+}
+
+//Layer names are kept to letters, digits and underscores
+fn word_layer(word: usize, scene: &Scene) -> String {
+    let letters = scene
+        .nodes
+        .iter()
+        .filter(|node| node.word == word)
+        .map(|node| node.letter.to_ascii_uppercase())
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>();
+    format!("WORD_{}_{letters}", word + 1)
+}
+
+/// The closed outline of a line drawn `half_width` to either side of it,
+/// with square ends.
+pub(crate) fn outline(line: &[Point], half_width: f32) -> Option<Vec<Point>> {
+    let sides = sides(line, half_width)?;
+    Some(
+        sides
+            .iter()
+            .map(|(left, _)| *left)
+            .chain(sides.iter().rev().map(|(_, right)| *right))
+            .collect(),
+    )
+}
+
+/// The points `half_width` to the left and right of every point of a line.
+pub(crate) fn sides(line: &[Point], half_width: f32) -> Option<Vec<(Point, Point)>> {
+    //Repeated points have no direction to offset along
+    let mut line = line.to_vec();
+    line.dedup_by(|a, b| a.distance(*b) <= f32::EPSILON);
+    if line.len() < 2 {
+        return None;
+    }
+
+    let last = line.len() - 1;
+    Some(
+        line.iter()
+            .enumerate()
+            .map(|(i, point)| {
+                let along = line[(i + 1).min(last)] - line[i.saturating_sub(1)];
+                let side = along.normalized().perpendicular() * half_width;
+                (*point + side, *point - side)
+            })
+            .collect(),
+    )
+}
+
+fn circle(dxf: &mut String, layer: &str, center: Point, radius: f32) {
+    pair(dxf, 0, "CIRCLE");
+    pair(dxf, 8, layer);
+    point(dxf, center);
+    pair(dxf, 40, num(radius, DECIMALS));
+}
+
+fn polyline(dxf: &mut String, layer: &str, points: &[Point]) {
+    pair(dxf, 0, "POLYLINE");
+    pair(dxf, 8, layer);
+    pair(dxf, 66, 1);
+    pair(dxf, 70, 1);
+    point(dxf, Point::default());
+    points.iter().for_each(|vertex| {
+        pair(dxf, 0, "VERTEX");
+        pair(dxf, 8, layer);
+        point(dxf, *vertex);
+    });
+    pair(dxf, 0, "SEQEND");
+    pair(dxf, 8, layer);
+}
+
+fn point(dxf: &mut String, point: Point) {
+    pair(dxf, 10, num(point.x, DECIMALS));
+    pair(dxf, 20, num(point.y, DECIMALS));
+    pair(dxf, 30, 0);
+}
+
+//DXF is a list of group codes, each followed by its value on the next line
+fn pair(dxf: &mut String, code: u16, value: impl std::fmt::Display) {
+    writeln!(dxf, "{code:>3}\n{value}").unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Glyph, GlyphStyle};
+
+    fn scene(phrase: &str) -> Scene {
+        Scene::new(&Glyph::new(phrase).unwrap(), &GlyphStyle::default())
+    }
+
+    //The group codes of a drawing with their values
+    fn pairs(dxf: &str) -> Vec<(u16, &str)> {
+        dxf.lines()
+            .collect::<Vec<_>>()
+            .chunks_exact(2)
+            .map(|pair| (pair[0].trim().parse().unwrap(), pair[1]))
+            .collect()
+    }
+
+    #[test]
+    fn every_word_has_a_layer() {
+        let scene = scene("hello world");
+        let dxf = to_dxf(&scene, &DxfOptions::default()).unwrap();
+        let pairs = pairs(&dxf);
+        assert!(!pairs.contains(&(9, "$INSUNITS")));
+        assert!(pairs
+            .windows(2)
+            .any(|pair| pair == [(9, "$MEASUREMENT"), (70, "1")]));
+
+        let layers = pairs
+            .windows(2)
+            .filter(|pair| pair[0] == (0, "LAYER"))
+            .map(|pair| pair[1].1)
+            .collect::<Vec<_>>();
+        assert_eq!(layers, ["WORD_1_HELLO", "WORD_2_WORLD", "BETWEEN"]);
+        //Each ring is a circle on the layer of its word
+        let circles = pairs
+            .windows(2)
+            .filter(|pair| pair[0] == (0, "CIRCLE"))
+            .map(|pair| pair[1].1)
+            .collect::<Vec<_>>();
+        layers[..2].iter().for_each(|layer| {
+            let count = circles.iter().filter(|circle| *circle == layer).count();
+            //The ring and a dot for every letter
+            assert_eq!(count, 6, "{layer}");
+        });
+        let entities = pairs
+            .iter()
+            .skip_while(|pair| **pair != (2, "ENTITIES"))
+            .filter(|(code, _)| *code == 8)
+            .map(|(_, layer)| *layer);
+        entities.for_each(|layer| assert!(layers.contains(&layer), "{layer}"));
+    }
+
+    #[test]
+    fn edges_are_narrowed_by_the_kerf() {
+        let scene = scene("hello");
+        let options = DxfOptions {
+            edge_width: 1.5,
+            kerf: 0.3,
+            ..DxfOptions::default()
+        };
+        let sheet = Sheet::new(&scene, &options).unwrap();
+        assert!((sheet.half_width - 0.6).abs() < 1e-6);
+
+        let line = [Point::new(0.0, 0.0), Point::new(10.0, 0.0)];
+        let outline = outline(&line, sheet.half_width).unwrap();
+        let (low, high) = outline
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(low, high), point| {
+                (low.min(point.y), high.max(point.y))
+            });
+        assert!((high - low - (options.edge_width - options.kerf)).abs() < 1e-6);
+
+        let too_wide = DxfOptions {
+            kerf: 1.5,
+            ..options
+        };
+        assert!(to_dxf(&scene, &too_wide).is_err());
+    }
+}
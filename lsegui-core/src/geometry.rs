@@ -0,0 +1,211 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Point;
+
+//How close an arc comes to the ring it hugs, as a share of the ring's own bulge
+const ARC_HUG: f32 = 0.8;
+
+/// The shape an edge between two different nodes is drawn with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EdgeStyle {
+    /// Bowed to the left of its direction, further for every parallel edge.
+    #[default]
+    Curve,
+    /// A straight line across the circle.
+    Chord,
+    /// Bowed towards the center of the word circle.
+    InwardArc,
+    /// Bowed out to run along the inside of the ring.
+    OutwardArc,
+}
+
+impl EdgeStyle {
+    pub const ALL: [EdgeStyle; 4] = [
+        EdgeStyle::Curve,
+        EdgeStyle::Chord,
+        EdgeStyle::InwardArc,
+        EdgeStyle::OutwardArc,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EdgeStyle::Curve => "curve",
+            EdgeStyle::Chord => "chord",
+            EdgeStyle::InwardArc => "inward arc",
+            EdgeStyle::OutwardArc => "outward arc",
+        }
+    }
+
+    /// Looks a style up by its [`EdgeStyle::name`], with `-` or `_` for spaces.
+    pub fn named(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase().replace(['-', '_'], " ");
+        EdgeStyle::ALL
+            .into_iter()
+            .find(|style| style.name() == name)
+    }
+
+    /// The quadratic Bezier an edge in this style is drawn as, like
+    /// [`edge_curve`]. Arcs bow relative to `ring`, the center and radius of
+    /// the word circle both nodes sit on, and fall back to a curve without one.
+    pub fn curve(
+        self,
+        [start, end]: [Point; 2],
+        [start_radius, end_radius]: [f32; 2],
+        curve_size: f32,
+        order: usize,
+        ring: Option<(Point, f32)>,
+    ) -> [Point; 3] {
+        let curve = edge_curve(start, end, start_radius, end_radius, curve_size, order);
+        let [edge_start, _, edge_end] = curve;
+        let middle = edge_start.lerp(edge_end, 0.5);
+        let (center, radius) = match (self, ring) {
+            (EdgeStyle::Chord, _) => return [edge_start, middle, edge_end],
+            (EdgeStyle::InwardArc | EdgeStyle::OutwardArc, Some(ring)) => ring,
+            //Arcs need a ring to bow against
+            _ => return curve,
+        };
+
+        //The bulge of the ring over the chord between the nodes, which a control point twice as far out matches
+        let half = start.distance(end) / 2.0;
+        let bulge = radius - (radius * radius - half * half).max(0.0).sqrt();
+        let outward = if middle.distance(center) > f32::EPSILON {
+            (middle - center).normalized()
+        } else {
+            (end - start).normalized().perpendicular()
+        };
+        let spread = curve_size * order as f32;
+        let control = match self {
+            EdgeStyle::InwardArc => middle - outward * (2.0 * bulge * ARC_HUG + spread),
+            _ => middle + outward * (2.0 * bulge * ARC_HUG - spread).max(0.0),
+        };
+        [edge_start, control, edge_end]
+    }
+}
+
+/// The quadratic Bezier an edge between two nodes is drawn as.
+///
+/// The curve starts and ends on the boundary of the node circles and bows to
+/// the left of its direction, further for every parallel edge (`order`).
+pub fn edge_curve(
+    start: Point,
+    end: Point,
+    start_radius: f32,
+    end_radius: f32,
+    curve_size: f32,
+    order: usize,
+) -> [Point; 3] {
+    let dir = (end - start).normalized();
+    let edge_start = start + dir * start_radius;
+    let edge_end = end - dir * end_radius;
+
+    let center_point = edge_start.lerp(edge_end, 0.5);
+    let control_point = center_point + dir.perpendicular() * curve_size * (order + 1) as f32;
+
+    [edge_start, control_point, edge_end]
+}
+
+/// The cubic Bezier an edge from a node to itself is drawn as.
+pub fn loop_curve(center: Point, radius: f32, loop_size: f32, order: usize) -> [Point; 4] {
+    let center_horizon_angle = std::f32::consts::PI / 4.;
+    let y_intersect = center.y - radius * center_horizon_angle.sin();
+
+    let edge_start = Point::new(center.x - radius * center_horizon_angle.cos(), y_intersect);
+    let edge_end = Point::new(center.x + radius * center_horizon_angle.cos(), y_intersect);
+
+    let loop_size = radius * (loop_size + order as f32);
+
+    let control_point1 = Point::new(center.x + loop_size, center.y - loop_size);
+    let control_point2 = Point::new(center.x - loop_size, center.y - loop_size);
+
+    [edge_end, control_point1, control_point2, edge_start]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EdgePath, Glyph, GlyphStyle, Scene};
+
+    fn close(a: Point, b: Point) -> bool {
+        a.distance(b) < 1e-3
+    }
+
+    //Two nodes a quarter of the way round a ring
+    const RING: (Point, f32) = (Point { x: 0.0, y: 0.0 }, 100.0);
+    const NODES: [Point; 2] = [Point { x: 100.0, y: 0.0 }, Point { x: 0.0, y: 100.0 }];
+
+    #[test]
+    fn every_style_runs_from_node_to_node() {
+        let [start, end] = NODES;
+        let dir = (end - start).normalized();
+        EdgeStyle::ALL.into_iter().for_each(|style| {
+            (0..3).for_each(|order| {
+                [Some(RING), None].into_iter().for_each(|ring| {
+                    let [from, _, to] = style.curve(NODES, [5.0, 8.0], 10.0, order, ring);
+                    assert!(close(from, start + dir * 5.0), "{}", style.name());
+                    assert!(close(to, end - dir * 8.0), "{}", style.name());
+                });
+            });
+        });
+    }
+
+    #[test]
+    fn styles_bow_their_own_way() {
+        let curve = |style: EdgeStyle| style.curve(NODES, [5.0; 2], 10.0, 0, Some(RING));
+        let [from, middle, to] = curve(EdgeStyle::Chord);
+        assert!(close(middle, from.lerp(to, 0.5)));
+
+        let from_center = |style| curve(style)[1].distance(RING.0);
+        let middle_from_center = middle.distance(RING.0);
+        assert!(from_center(EdgeStyle::InwardArc) < middle_from_center);
+        assert!(from_center(EdgeStyle::OutwardArc) > middle_from_center);
+        //Without a ring to bow against, arcs are drawn as curves
+        assert_eq!(
+            EdgeStyle::InwardArc.curve(NODES, [5.0; 2], 10.0, 1, None),
+            EdgeStyle::Curve.curve(NODES, [5.0; 2], 10.0, 1, None)
+        );
+    }
+
+    #[test]
+    fn edge_styles_override_the_glyph_style() {
+        let mut glyph = Glyph::new("hello").unwrap();
+        //Scene edges come in the order of the glyph's, which are those of the graph
+        let (i, edge) = glyph
+            .graph()
+            .edge_indices()
+            .enumerate()
+            .find(|(_, edge)| {
+                let (source, target) = glyph.graph().edge_endpoints(*edge).unwrap();
+                source != target
+            })
+            .expect("hello has edges between letters");
+        let is_chord = |scene: &Scene, i: usize| match scene.edges[i].path {
+            EdgePath::Quadratic([from, middle, to]) => close(middle, from.lerp(to, 0.5)),
+            EdgePath::Cubic(_) => panic!("not a loop"),
+        };
+
+        glyph.set_edge_style(edge, Some(EdgeStyle::Chord));
+        let scene = Scene::new(&glyph, &GlyphStyle::default());
+        assert!(is_chord(&scene, i));
+        assert_eq!(
+            scene
+                .edges
+                .iter()
+                .enumerate()
+                .filter(
+                    |(i, edge)| matches!(edge.path, EdgePath::Quadratic(_)) && is_chord(&scene, *i)
+                )
+                .count(),
+            1
+        );
+
+        glyph.set_edge_style(edge, Some(EdgeStyle::Curve));
+        let chords = GlyphStyle {
+            edge_style: EdgeStyle::Chord,
+            ..GlyphStyle::default()
+        };
+        assert!(!is_chord(&Scene::new(&glyph, &chords), i));
+
+        glyph.set_edge_style(edge, None);
+        assert!(is_chord(&Scene::new(&glyph, &chords), i));
+    }
+}
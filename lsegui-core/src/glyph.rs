@@ -0,0 +1,423 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use petgraph::stable_graph::{EdgeIndex, NodeIndex, StableGraph};
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
+
+use crate::connections::{connect_between, connect_word};
+use crate::phrase::split_words;
+use crate::{
+    build_connections, CircleLayout, ConnectionTable, EdgeKind, EdgeStyle, Phrase, Point, Word,
+};
+
+/// Why a phrase couldn't be turned into a glyph.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GlyphError {
+    /// There are no letters in the phrase.
+    EmptyPhrase,
+    /// The connection table has no entry for a letter of the phrase.
+    UnknownLetter(char),
+    /// Every arrangement tried had two word circles overlapping.
+    NoArrangement,
+}
+
+impl fmt::Display for GlyphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GlyphError::EmptyPhrase => write!(f, "the phrase has no letters to draw"),
+            GlyphError::UnknownLetter(letter) => {
+                write!(f, "the connection table has no entry for {letter}")
+            }
+            GlyphError::NoArrangement => {
+                write!(f, "the word circles can't be arranged without overlapping")
+            }
+        }
+    }
+}
+
+impl std::error::Error for GlyphError {}
+
+/// A phrase turned into its connection graph and word circle layout.
+#[derive(Clone, Debug)]
+pub struct Glyph {
+    pub phrase: Phrase,
+    pub circles: CircleLayout,
+    /// Edges drawn in another style than the rest, by source, target and
+    /// order as given by [`Glyph::edges`].
+    pub edge_styles: HashMap<(NodeIndex<u32>, NodeIndex<u32>, usize), EdgeStyle>,
+}
+
+impl Glyph {
+    /// Builds the glyph of `phrase` with the bundled connection table.
+    pub fn new(phrase: &str) -> Result<Self, GlyphError> {
+        Glyph::with_rules(phrase, &ConnectionTable::default())
+    }
+
+    /// Builds the glyph of `phrase`, connecting letters as `rules` describes.
+    pub fn with_rules(phrase: &str, rules: &ConnectionTable) -> Result<Self, GlyphError> {
+        let mut phrase = Phrase::new(phrase)?;
+        check_letters(phrase.phrase_words.iter().map(|word| &word.word), rules)?;
+
+        phrase.analyse_phrase();
+
+        build_connections(&mut phrase, rules);
+
+        let mut circles = CircleLayout::new();
+        circles.layout(&phrase)?;
+
+        Ok(Glyph {
+            phrase,
+            circles,
+            edge_styles: HashMap::new(),
+        })
+    }
+
+    /// Rebuilds the glyph for an edited `phrase`, only connecting the words
+    /// that changed. Words kept from the old phrase keep their nodes, and
+    /// nodes moved by hand stay where they were on their circle.
+    ///
+    /// `rules` should be the table the glyph was built with, the kept words
+    /// aren't connected again. New circles are laid out around pinned ones,
+    /// and pinned circles overlapping each other give
+    /// [`GlyphError::NoArrangement`]. On error the glyph is left as it was.
+    pub fn update(&mut self, phrase: &str, rules: &ConnectionTable) -> Result<(), GlyphError> {
+        let words = split_words(phrase)?;
+        check_letters(words.iter(), rules)?;
+
+        let old_words = &self.phrase.phrase_words;
+        let kept = common_words(old_words, &words);
+        let mut graph = self.phrase.graph.clone();
+        //Edges between words depend on the neighbours, which may have changed
+        graph.retain_edges(|graph, edge| graph[edge] == EdgeKind::Within);
+        old_words
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !kept.contains(&Some(*i)))
+            .flat_map(|(_, word)| word.nodes.iter())
+            .for_each(|node| {
+                graph.remove_node(*node);
+            });
+
+        let phrase_words = words
+            .into_iter()
+            .zip(&kept)
+            .map(|(word, kept)| match kept {
+                Some(i) => Word::new(word, old_words[*i].nodes.clone()),
+                None => {
+                    let nodes = word.chars().map(|_| graph.add_node(())).collect();
+                    let word = Word::new(word, nodes);
+                    connect_word(&mut graph, &word, rules);
+                    word
+                }
+            })
+            .collect::<Vec<_>>();
+        connect_between(&mut graph, &phrase_words, rules);
+        let mut phrase = Phrase {
+            phrase_words,
+            graph,
+        };
+        phrase.analyse_phrase();
+
+        //New circles are laid out around the pinned ones
+        let pinned = kept
+            .iter()
+            .map(|kept| {
+                self.circles
+                    .circle_list
+                    .get((*kept)?)
+                    .filter(|old| old.pinned)
+                    .cloned()
+            })
+            .collect::<Vec<_>>();
+        let mut circles = CircleLayout::new();
+        circles.layout_around(&phrase, &pinned)?;
+
+        //Pinned circles stay put, and moved nodes keep their place relative to their circle
+        circles
+            .circle_list
+            .iter_mut()
+            .zip(&kept)
+            .filter_map(|(circle, kept)| Some((circle, self.circles.circle_list.get((*kept)?)?)))
+            .for_each(|(circle, old)| {
+                if old.pinned {
+                    circle.set_placement(old.center, old.rotation);
+                }
+                let moved = old
+                    .node_pos_list
+                    .node_pos_list
+                    .iter()
+                    .map(|old_pos| {
+                        old_pos
+                            .moved
+                            .then(|| (circle.from_local(old.to_local(old_pos.pos)), old_pos.angle))
+                    })
+                    .collect::<Vec<_>>();
+                circle
+                    .node_pos_list
+                    .node_pos_list
+                    .iter_mut()
+                    .zip(moved)
+                    .filter_map(|(node_pos, moved)| Some((node_pos, moved?)))
+                    .for_each(|(node_pos, (pos, angle))| {
+                        node_pos.pos = pos;
+                        node_pos.angle = angle;
+                        node_pos.moved = true;
+                    });
+            });
+        //Circles pinned on top of each other can't be laid out around
+        if circles.has_overlaps() {
+            return Err(GlyphError::NoArrangement);
+        }
+
+        let mut glyph = Glyph {
+            phrase,
+            circles,
+            edge_styles: HashMap::new(),
+        };
+        //Kept words keep their nodes, so the styles of their edges still apply
+        glyph.edge_styles = glyph
+            .edges()
+            .filter_map(|(source, target, order, _)| {
+                let key = (source, target, order);
+                Some((key, *self.edge_styles.get(&key)?))
+            })
+            .collect();
+        *self = glyph;
+        Ok(())
+    }
+
+    /// The directed letter connections, one node per letter of every word.
+    pub fn graph(&self) -> &StableGraph<(), EdgeKind> {
+        &self.phrase.graph
+    }
+
+    /// Every letter node with its letter and laid out position.
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeIndex<u32>, char, Point)> + '_ {
+        self.circles.circle_list.iter().flat_map(|circle| {
+            circle
+                .word
+                .word
+                .chars()
+                .zip(circle.node_pos_list.node_pos_list.iter())
+                .map(|(letter, node_pos)| (node_pos.node, letter, node_pos.pos))
+        })
+    }
+
+    /// Draws `edge` in `style`, or like the rest of the edges with `None`.
+    pub fn set_edge_style(&mut self, edge: EdgeIndex<u32>, style: Option<EdgeStyle>) {
+        let Some((source, target)) = self.phrase.graph.edge_endpoints(edge) else {
+            return;
+        };
+        //Counted the same way as in edges
+        let order = self
+            .phrase
+            .graph
+            .edge_references()
+            .filter(|other| other.source() == source && other.target() == target)
+            .take_while(|other| other.id() != edge)
+            .count();
+        match style {
+            Some(style) => self.edge_styles.insert((source, target, order), style),
+            None => self.edge_styles.remove(&(source, target, order)),
+        };
+    }
+
+    /// Every edge as its source, target, how many edges with the same
+    /// source and target come before it, and what it joins.
+    pub fn edges(
+        &self,
+    ) -> impl Iterator<Item = (NodeIndex<u32>, NodeIndex<u32>, usize, EdgeKind)> + '_ {
+        let mut orders = HashMap::new();
+        self.phrase.graph.edge_references().map(move |edge| {
+            let order = orders.entry((edge.source(), edge.target())).or_insert(0);
+            *order += 1;
+            (edge.source(), edge.target(), *order - 1, *edge.weight())
+        })
+    }
+}
+
+//Without an entry a letter would silently get a made up connection
+fn check_letters<'a>(
+    words: impl Iterator<Item = &'a String>,
+    rules: &ConnectionTable,
+) -> Result<(), GlyphError> {
+    match words
+        .flat_map(|word| word.chars())
+        .find(|letter| rules.letters().all(|(known, _)| known != *letter))
+    {
+        Some(letter) => Err(GlyphError::UnknownLetter(letter)),
+        None => Ok(()),
+    }
+}
+
+//For every new word, the old word it is kept from, matching as many words in order as possible
+fn common_words(old: &[Word], new: &[String]) -> Vec<Option<usize>> {
+    //lengths[i][j] is the longest common run of old[i..] and new[j..]
+    let mut lengths = vec![vec![0; new.len() + 1]; old.len() + 1];
+    (0..old.len()).rev().for_each(|i| {
+        (0..new.len()).rev().for_each(|j| {
+            lengths[i][j] = if old[i].word == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        });
+    });
+
+    let mut kept = vec![None; new.len()];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i].word == new[j] {
+            kept[j] = Some(i);
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phrases_without_letters_are_empty() {
+        ["", "   ", "\t\n", "123 456", "!? ... --", "ÆØÅ ñ"]
+            .into_iter()
+            .for_each(|phrase| {
+                assert_eq!(
+                    Glyph::new(phrase).unwrap_err(),
+                    GlyphError::EmptyPhrase,
+                    "{phrase:?}"
+                );
+            });
+    }
+
+    #[test]
+    fn letters_missing_from_the_table_are_named() {
+        let rules = ConnectionTable::parse("[connections]\nA = \"B\"\nB = \"\"\n").unwrap();
+        assert!(Glyph::with_rules("abba ab", &rules).is_ok());
+        assert_eq!(
+            Glyph::with_rules("ab cab", &rules).unwrap_err(),
+            GlyphError::UnknownLetter('C')
+        );
+    }
+
+    #[test]
+    fn failed_updates_leave_the_glyph_alone() {
+        let rules = ConnectionTable::default();
+        let mut glyph = Glyph::new("hello world").unwrap();
+        //Pinned on top of the first word's circle
+        let center = glyph.circles.circle_list[0].center;
+        glyph.circles.circle_list[1].set_placement(center, 0.0);
+        let nodes = glyph.nodes().collect::<Vec<_>>();
+
+        [
+            ("", GlyphError::EmptyPhrase),
+            ("  42  ", GlyphError::EmptyPhrase),
+            ("hello world", GlyphError::NoArrangement),
+            ("hello big world", GlyphError::NoArrangement),
+        ]
+        .into_iter()
+        .for_each(|(phrase, error)| {
+            assert_eq!(
+                glyph.update(phrase, &rules).unwrap_err(),
+                error,
+                "{phrase:?}"
+            );
+            assert_eq!(glyph.nodes().collect::<Vec<_>>(), nodes, "{phrase:?}");
+        });
+
+        let rules = ConnectionTable::parse("[connections]\nH = \"\"\n").unwrap();
+        assert_eq!(
+            glyph.update("hello", &rules).unwrap_err(),
+            GlyphError::UnknownLetter('E')
+        );
+    }
+
+    fn words(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn common_words_keep_their_order() {
+        let old = Phrase::new("the quick brown fox").unwrap().phrase_words;
+        let cases: &[(&[&str], &[Option<usize>])] = &[
+            (
+                &["THE", "QUICK", "BROWN", "FOX"],
+                &[Some(0), Some(1), Some(2), Some(3)],
+            ),
+            (
+                &["THE", "VERY", "QUICK", "BROWN", "FOX"],
+                &[Some(0), None, Some(1), Some(2), Some(3)],
+            ),
+            (&["THE", "BROWN", "FOX"], &[Some(0), Some(2), Some(3)]),
+            (
+                &["THE", "SLOW", "BROWN", "FOX"],
+                &[Some(0), None, Some(2), Some(3)],
+            ),
+            //Only one of two swapped words can be kept
+            (&["FOX", "THE"], &[Some(3), None]),
+            (&["CAT"], &[None]),
+        ];
+        cases.iter().for_each(|(new, kept)| {
+            assert_eq!(common_words(&old, &words(new)), *kept, "{new:?}");
+        });
+    }
+
+    #[test]
+    fn edits_keep_moved_nodes_of_other_words() {
+        let rules = ConnectionTable::default();
+        let mut original = Glyph::new("hello big world").unwrap();
+        //Move the second letter of the first and last words off their usual place
+        let moved = [0, 2].map(|word| {
+            let circle = &original.circles.circle_list[word];
+            let node_pos = &circle.node_pos_list.node_pos_list[1];
+            (node_pos.node, node_pos.pos + Point::new(12.0, -7.0))
+        });
+        moved
+            .iter()
+            .for_each(|(node, pos)| original.circles.set_node_pos(*node, *pos));
+        //Where they sit relative to their circles, which may be laid out elsewhere after the edit
+        let local = |glyph: &Glyph| {
+            moved
+                .iter()
+                .map(|(node, _)| {
+                    let circle = glyph
+                        .circles
+                        .circle_list
+                        .iter()
+                        .find(|circle| circle.word.nodes.contains(node))
+                        .expect("the word is kept");
+                    let node_pos = circle
+                        .node_pos_list
+                        .node_pos_list
+                        .iter()
+                        .find(|node_pos| node_pos.node == *node)
+                        .unwrap();
+                    assert!(node_pos.moved);
+                    circle.to_local(node_pos.pos)
+                })
+                .collect::<Vec<_>>()
+        };
+        let before = local(&original);
+
+        ["hello very big world", "hello world", "hello small world"]
+            .into_iter()
+            .for_each(|phrase| {
+                let mut glyph = original.clone();
+                glyph.update(phrase, &rules).unwrap();
+                assert_eq!(glyph.phrase.phrase_words.len(), phrase.split(' ').count());
+                local(&glyph)
+                    .iter()
+                    .zip(&before)
+                    .for_each(|(after, before)| {
+                        assert!(after.distance(*before) < 0.01, "{phrase}");
+                    });
+            });
+    }
+}
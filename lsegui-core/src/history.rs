@@ -0,0 +1,120 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Project;
+
+//Edits kept before the oldest ones are forgotten
+const HISTORY_LIMIT: usize = 100;
+
+/// Edits that can be undone and redone, kept as snapshots of the project
+/// on either side of each edit.
+///
+/// Snapshots never carry a history of their own.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct History {
+    undo: VecDeque<Project>,
+    redo: Vec<Project>,
+}
+
+impl History {
+    /// Remembers the project as it was before an edit. Edits that were
+    /// undone can't be redone after this.
+    pub fn record(&mut self, before: Project) {
+        self.undo.push_back(without_history(before));
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.pop_front();
+        }
+        self.redo.clear();
+    }
+
+    /// Steps back from `current`, returning the project to show instead.
+    pub fn undo(&mut self, current: Project) -> Option<Project> {
+        let previous = self.undo.pop_back()?;
+        self.redo.push(without_history(current));
+        Some(previous)
+    }
+
+    /// Steps forward again from `current` after an undo.
+    pub fn redo(&mut self, current: Project) -> Option<Project> {
+        let next = self.redo.pop()?;
+        self.undo.push_back(without_history(current));
+        Some(next)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.can_undo() && !self.can_redo()
+    }
+}
+
+fn without_history(project: Project) -> Project {
+    Project {
+        history: History::default(),
+        ..project
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConnectionTable, Glyph, Theme};
+
+    fn project(phrase: &str) -> Project {
+        let glyph = Glyph::new(phrase).unwrap();
+        let rules = ConnectionTable::default();
+        Project::new(phrase, &glyph, "default", &rules, &Theme::dark())
+    }
+
+    #[test]
+    fn undo_and_redo_step_through_edits_in_order() {
+        let (a, b, c) = (project("a"), project("b"), project("c"));
+        let mut history = History::default();
+        history.record(a.clone());
+        history.record(b.clone());
+
+        assert_eq!(history.undo(c.clone()), Some(b.clone()));
+        assert_eq!(history.undo(b.clone()), Some(a.clone()));
+        assert_eq!(history.undo(a.clone()), None);
+        assert_eq!(history.redo(a.clone()), Some(b.clone()));
+        assert_eq!(history.redo(b.clone()), Some(c.clone()));
+        assert_eq!(history.redo(c), None);
+        assert!(history.can_undo() && !history.can_redo());
+    }
+
+    #[test]
+    fn new_edits_clear_what_could_be_redone() {
+        let mut history = History::default();
+        history.record(project("a"));
+        history.undo(project("b")).unwrap();
+        assert!(history.can_redo());
+
+        history.record(project("a"));
+        assert!(!history.can_redo());
+        assert_eq!(history.redo(project("c")), None);
+    }
+
+    #[test]
+    fn only_the_latest_edits_are_kept() {
+        let projects = (0..HISTORY_LIMIT + 5)
+            .map(|i| project(&"a".repeat(i + 1)))
+            .collect::<Vec<_>>();
+        let mut history = History::default();
+        projects
+            .iter()
+            .for_each(|project| history.record(project.clone()));
+
+        let current = project("z");
+        let undone = std::iter::from_fn(|| history.undo(current.clone())).collect::<Vec<_>>();
+        assert_eq!(undone.len(), HISTORY_LIMIT);
+        assert_eq!(undone.last(), Some(&projects[5]));
+        assert_eq!(undone[0], projects[HISTORY_LIMIT + 4]);
+    }
+}
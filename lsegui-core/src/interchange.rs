@@ -0,0 +1,362 @@
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use crate::number::num;
+use crate::{EdgeKind, Glyph, Point};
+
+//Decimals positions are written with
+const DECIMALS: usize = 4;
+
+/// The JSON Schema of what [`to_graph_json`] writes.
+pub const GRAPH_SCHEMA: &str = include_str!("../schemas/graph.schema.json");
+
+//Bumped whenever a change to the format would stop older readers reading it correctly
+const GRAPH_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct GraphData {
+    version: u32,
+    phrase: String,
+    words: Vec<WordData>,
+    nodes: Vec<NodeData>,
+    edges: Vec<EdgeData>,
+}
+
+#[derive(Serialize)]
+struct WordData {
+    index: usize,
+    word: String,
+    center: Point,
+    radius: f32,
+}
+
+#[derive(Serialize)]
+struct NodeData {
+    id: usize,
+    letter: char,
+    word: usize,
+    letter_index: usize,
+    x: f32,
+    y: f32,
+}
+
+#[derive(Serialize)]
+struct EdgeData {
+    source: usize,
+    target: usize,
+    kind: &'static str,
+}
+
+impl GraphData {
+    fn new(glyph: &Glyph) -> Self {
+        let circles = &glyph.circles.circle_list;
+        GraphData {
+            version: GRAPH_VERSION,
+            phrase: circles
+                .iter()
+                .map(|circle| circle.word.word.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+            words: circles
+                .iter()
+                .enumerate()
+                .map(|(index, circle)| WordData {
+                    index,
+                    word: circle.word.word.clone(),
+                    center: circle.center,
+                    radius: circle.radius,
+                })
+                .collect(),
+            nodes: circles
+                .iter()
+                .enumerate()
+                .flat_map(|(word, circle)| {
+                    circle
+                        .word
+                        .word
+                        .chars()
+                        .zip(&circle.node_pos_list.node_pos_list)
+                        .enumerate()
+                        .map(move |(letter_index, (letter, node_pos))| NodeData {
+                            id: node_pos.node.index(),
+                            letter,
+                            word,
+                            letter_index,
+                            x: node_pos.pos.x,
+                            y: node_pos.pos.y,
+                        })
+                })
+                .collect(),
+            edges: glyph
+                .edges()
+                .map(|(source, target, _, kind)| EdgeData {
+                    source: source.index(),
+                    target: target.index(),
+                    kind: kind_name(kind),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Writes the connection graph of a glyph as JSON, as described by
+/// [`GRAPH_SCHEMA`].
+///
+/// Every letter node comes with its word and where the layout put it, in the
+/// units of the layout with y going down as the app draws them.
+pub fn to_graph_json(glyph: &Glyph) -> String {
+    serde_json::to_string_pretty(&GraphData::new(glyph)).expect("graphs should always serialize")
+}
+
+/// Writes the connection graph of a glyph as GraphViz DOT, with a cluster
+/// for every word.
+///
+/// Nodes are pinned where the layout put them, in points with y going up,
+/// so `neato -n` draws the glyph's graph the way the app lays it out.
+pub fn to_dot(glyph: &Glyph) -> String {
+    let graph = GraphData::new(glyph);
+    let mut dot = String::new();
+
+    writeln!(dot, "digraph glyph {{").unwrap();
+    writeln!(dot, "  label=\"{}\";", dot_text(&graph.phrase)).unwrap();
+    writeln!(dot, "  node [shape=circle];").unwrap();
+    graph.words.iter().for_each(|word| {
+        writeln!(dot, "  subgraph cluster_{} {{", word.index).unwrap();
+        writeln!(dot, "    label=\"{}\";", dot_text(&word.word)).unwrap();
+        graph
+            .nodes
+            .iter()
+            .filter(|node| node.word == word.index)
+            .for_each(|node| {
+                writeln!(
+                    dot,
+                    "    n{} [label=\"{}\", word={}, letter_index={}, pos=\"{},{}!\"];",
+                    node.id,
+                    dot_text(&node.letter.to_string()),
+                    node.word,
+                    node.letter_index,
+                    num(node.x, DECIMALS),
+                    num(-node.y, DECIMALS)
+                )
+                .unwrap();
+            });
+        writeln!(dot, "  }}").unwrap();
+    });
+    graph.edges.iter().for_each(|edge| {
+        writeln!(
+            dot,
+            "  n{} -> n{} [kind={}];",
+            edge.source, edge.target, edge.kind
+        )
+        .unwrap();
+    });
+    writeln!(dot, "}}").unwrap();
+    dot
+}
+
+/// Writes the connection graph of a glyph as GraphML, with the same node and
+/// edge data as [`to_graph_json`].
+pub fn to_graphml(glyph: &Glyph) -> String {
+    let graph = GraphData::new(glyph);
+    let mut graphml = String::new();
+
+    writeln!(graphml, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+    writeln!(
+        graphml,
+        r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+    )
+    .unwrap();
+    [
+        ("phrase", "graph", "string"),
+        ("letter", "node", "string"),
+        ("word", "node", "int"),
+        ("word_text", "node", "string"),
+        ("letter_index", "node", "int"),
+        ("x", "node", "float"),
+        ("y", "node", "float"),
+        ("kind", "edge", "string"),
+    ]
+    .iter()
+    .for_each(|(name, target, kind)| {
+        writeln!(
+            graphml,
+            r#"  <key id="{name}" for="{target}" attr.name="{name}" attr.type="{kind}"/>"#
+        )
+        .unwrap();
+    });
+    writeln!(graphml, r#"  <graph id="glyph" edgedefault="directed">"#).unwrap();
+    writeln!(
+        graphml,
+        r#"    <data key="phrase">{}</data>"#,
+        xml_text(&graph.phrase)
+    )
+    .unwrap();
+    graph.nodes.iter().for_each(|node| {
+        writeln!(graphml, r#"    <node id="n{}">"#, node.id).unwrap();
+        [
+            ("letter", xml_text(&node.letter.to_string())),
+            ("word", node.word.to_string()),
+            ("word_text", xml_text(&graph.words[node.word].word)),
+            ("letter_index", node.letter_index.to_string()),
+            ("x", num(node.x, DECIMALS)),
+            ("y", num(node.y, DECIMALS)),
+        ]
+        .iter()
+        .for_each(|(key, value)| {
+            writeln!(graphml, r#"      <data key="{key}">{value}</data>"#).unwrap();
+        });
+        writeln!(graphml, "    </node>").unwrap();
+    });
+    graph.edges.iter().for_each(|edge| {
+        writeln!(
+            graphml,
+            r#"    <edge source="n{}" target="n{}"><data key="kind">{}</data></edge>"#,
+            edge.source, edge.target, edge.kind
+        )
+        .unwrap();
+    });
+    writeln!(graphml, "  </graph>").unwrap();
+    writeln!(graphml, "</graphml>").unwrap();
+    graphml
+}
+
+//Text inside a quoted DOT string. Words are only ever ASCII letters now,
+//but the formats shouldn't break if they ever aren't.
+fn dot_text(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+//Text inside an XML element or attribute
+fn xml_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn kind_name(kind: EdgeKind) -> &'static str {
+    match kind {
+        EdgeKind::Within => "within",
+        EdgeKind::Between => "between",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+
+    //Checks the parts of JSON Schema the graph schema is written with
+    fn check(root: &Value, schema: &Value, value: &Value, at: &str) {
+        if let Some(reference) = schema["$ref"].as_str() {
+            let name = reference.trim_start_matches("#/$defs/");
+            return check(root, &root["$defs"][name], value, at);
+        }
+        if let Some(kind) = schema["type"].as_str() {
+            let matches = match kind {
+                "object" => value.is_object(),
+                "array" => value.is_array(),
+                "string" => value.is_string(),
+                "integer" => value.is_u64() || value.is_i64(),
+                "number" => value.is_number(),
+                _ => panic!("{at} has the unknown type {kind}"),
+            };
+            assert!(matches, "{at} should be of type {kind}: {value}");
+        }
+        if !schema["const"].is_null() {
+            assert_eq!(value, &schema["const"], "{at}");
+        }
+        if let Some(allowed) = schema["enum"].as_array() {
+            assert!(allowed.contains(value), "{at} is not one of {allowed:?}");
+        }
+        if let Some(minimum) = schema["minimum"].as_f64() {
+            assert!(value.as_f64().unwrap() >= minimum, "{at}");
+        }
+        if let Some(text) = value.as_str() {
+            let length = text.chars().count() as u64;
+            assert!(length >= schema["minLength"].as_u64().unwrap_or(0), "{at}");
+            assert!(
+                length <= schema["maxLength"].as_u64().unwrap_or(u64::MAX),
+                "{at}"
+            );
+        }
+        if let Some(required) = schema["required"].as_array() {
+            required.iter().for_each(|key| {
+                let key = key.as_str().unwrap();
+                assert!(value.get(key).is_some(), "{at} is missing {key}");
+            });
+        }
+        if let Some(properties) = schema["properties"].as_object() {
+            properties.iter().for_each(|(key, property)| {
+                if let Some(field) = value.get(key) {
+                    check(root, property, field, &format!("{at}.{key}"));
+                }
+            });
+        }
+        if let Some(items) = value.as_array() {
+            items.iter().enumerate().for_each(|(i, item)| {
+                check(root, &schema["items"], item, &format!("{at}[{i}]"));
+            });
+        }
+    }
+
+    #[test]
+    fn json_matches_the_schema() {
+        let schema = serde_json::from_str::<Value>(GRAPH_SCHEMA).unwrap();
+        ["hello world", "a", "the quick brown fox"]
+            .into_iter()
+            .for_each(|phrase| {
+                let glyph = Glyph::new(phrase).unwrap();
+                let graph = serde_json::from_str::<Value>(&to_graph_json(&glyph)).unwrap();
+                check(&schema, &schema, &graph, phrase);
+                assert_eq!(
+                    graph["nodes"].as_array().unwrap().len(),
+                    glyph.nodes().count()
+                );
+                assert_eq!(
+                    graph["edges"].as_array().unwrap().len(),
+                    glyph.edges().count()
+                );
+            });
+    }
+
+    #[test]
+    fn every_node_and_edge_is_written() {
+        let glyph = Glyph::new("hello world").unwrap();
+        let nodes = glyph.nodes().count();
+        let edges = glyph.edges().count();
+        assert!(nodes > 0 && edges > 0);
+
+        let dot = to_dot(&glyph);
+        assert_eq!(dot.matches("[label=").count(), nodes);
+        assert_eq!(dot.matches(" -> ").count(), edges);
+        assert_eq!(dot.matches("subgraph cluster_").count(), 2);
+
+        let graphml = to_graphml(&glyph);
+        assert_eq!(graphml.matches("<node id=").count(), nodes);
+        assert_eq!(graphml.matches("<edge source=").count(), edges);
+    }
+
+    #[test]
+    fn labels_are_escaped() {
+        let mut glyph = Glyph::new("hello world").unwrap();
+        glyph.circles.circle_list[0].word.word = String::from("H\"&<O");
+
+        let dot = to_dot(&glyph);
+        assert!(dot.contains(r#"label="H\"&<O WORLD";"#), "{dot}");
+        assert!(dot.contains(r#"[label="\"","#), "{dot}");
+        assert!(dot.contains(r#"[label="&","#), "{dot}");
+
+        let graphml = to_graphml(&glyph);
+        assert!(graphml.contains(r#"<data key="phrase">H&quot;&amp;&lt;O WORLD</data>"#));
+        assert!(graphml.contains(r#"<data key="letter">&quot;</data>"#));
+        assert!(graphml.contains(r#"<data key="letter">&amp;</data>"#));
+        assert!(!graphml.contains("\"&<"));
+
+        let json = serde_json::from_str::<Value>(&to_graph_json(&glyph)).unwrap();
+        assert_eq!(json["words"][0]["word"], "H\"&<O");
+    }
+}
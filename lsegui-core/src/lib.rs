@@ -0,0 +1,59 @@
+//! Headless engine for the Gall Language Art form of Lost Skies.
+//!
+//! Turns a phrase into a connection graph between its letters and a set of
+//! word circles with a position for every letter node, without depending on
+//! any rendering crate.
+//!
+//! ```
+//! let glyph = lsegui_core::Glyph::new("Default Phrase")?;
+//! for (node, letter, pos) in glyph.nodes() {
+//!     println!("{node:?} {letter} at {}, {}", pos.x, pos.y);
+//! }
+//! # Ok::<(), lsegui_core::GlyphError>(())
+//! ```
+
+mod circle_layout;
+mod connections;
+mod decode;
+mod dxf;
+mod geometry;
+mod glyph;
+mod history;
+mod interchange;
+mod letters;
+mod mesh;
+mod number;
+mod phrase;
+mod plot;
+mod png;
+mod point;
+mod project;
+mod scene;
+mod share;
+mod stencil;
+mod style;
+mod svg;
+mod theme;
+
+pub use circle_layout::{Circle, CircleLayout, NodePos, NodePosList};
+pub use connections::{build_connections, ConnectionTable, EdgeKind, RuleError, DEFAULT_RULES};
+pub use decode::{decode, reads_as, DecodedWord};
+pub use dxf::{to_dxf, DxfOptions};
+pub use geometry::{edge_curve, loop_curve, EdgeStyle};
+pub use glyph::{Glyph, GlyphError};
+pub use history::History;
+pub use interchange::{to_dot, to_graph_json, to_graphml, GRAPH_SCHEMA};
+pub use mesh::{to_obj, to_stl, BaseShape, ExtrudeOptions};
+pub use phrase::{NodeLayout, Phrase, Word};
+pub use plot::{plot_paths, to_gcode, to_hpgl, PlotOptions};
+pub use png::{to_png, RasterOptions};
+pub use point::Point;
+pub use project::{CircleState, EdgeStyleState, NodeState, Project, ProjectError};
+pub use scene::{Bounds, Dot, EdgePath, EdgeStroke, Label, Ring, Scene};
+pub use share::SharedGlyph;
+pub use stencil::{to_stencil_dxf, to_stencil_svg};
+pub use style::{Color, ColorScheme, GlyphStyle, LabelFont, DEFAULT_PALETTE};
+pub use svg::to_svg;
+pub use theme::Theme;
+
+pub use petgraph;
@@ -0,0 +1,445 @@
+use std::fmt::Write;
+
+use crate::dxf::sides;
+use crate::number::num;
+use crate::scene::flatten_circle;
+use crate::{Point, Scene};
+
+//Decimals of a millimetre vertices are written with
+const DECIMALS: usize = 4;
+
+/// The shape a printed glyph stands on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BaseShape {
+    /// No base, so every stroke is printed straight onto the bed.
+    None,
+    /// A round medallion around the word circles.
+    #[default]
+    Disc,
+    /// A rectangle around the word circles.
+    Plate,
+}
+
+impl BaseShape {
+    pub const ALL: [BaseShape; 3] = [BaseShape::None, BaseShape::Disc, BaseShape::Plate];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BaseShape::None => "none",
+            BaseShape::Disc => "disc",
+            BaseShape::Plate => "plate",
+        }
+    }
+
+    /// Looks a shape up by its [`BaseShape::name`].
+    pub fn named(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        BaseShape::ALL
+            .into_iter()
+            .find(|shape| shape.name() == name)
+    }
+}
+
+/// Size and heights of a glyph extruded for 3D printing, in millimetres.
+///
+/// How thick the strokes are comes from the scene, and so from the theme it
+/// was drawn with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExtrudeOptions {
+    /// How wide the word circles come out, from the outside of the leftmost
+    /// ring to the outside of the rightmost one.
+    pub width: f32,
+    /// How far the strokes stand above the base, or above the bed without one.
+    pub height: f32,
+    pub base: BaseShape,
+    pub base_height: f32,
+    /// How far the base reaches out beyond the word circles.
+    pub base_margin: f32,
+    /// How far the straight steps of a curve may stray from it.
+    pub tolerance: f32,
+}
+
+impl Default for ExtrudeOptions {
+    //A medallion that fits the palm of a hand
+    fn default() -> Self {
+        ExtrudeOptions {
+            width: 80.0,
+            height: 2.0,
+            base: BaseShape::Disc,
+            base_height: 2.0,
+            base_margin: 3.0,
+            tolerance: 0.05,
+        }
+    }
+}
+
+/// Writes a scene as a binary STL for 3D printing.
+///
+/// The word circles are centered on the origin, in millimetres with z going
+/// up from the bed. Rings, node dots and edges are each a closed solid of
+/// their own standing on the base, overlapping it and each other where they
+/// meet, which slicers merge into one when they print them. Labels are left out.
+pub fn to_stl(scene: &Scene, options: &ExtrudeOptions) -> Result<Vec<u8>, String> {
+    let mesh = Mesh::extrude(scene, options)?;
+    let mut stl = Vec::with_capacity(84 + mesh.triangles.len() * 50);
+
+    stl.extend_from_slice(b"LSegui glyph, millimetres");
+    stl.resize(80, b' ');
+    stl.extend((mesh.triangles.len() as u32).to_le_bytes());
+    mesh.triangles.iter().for_each(|triangle| {
+        let corners = triangle.map(|vertex| mesh.vertices[vertex]);
+        std::iter::once(normal(&corners))
+            .chain(corners)
+            .flatten()
+            .for_each(|value| stl.extend(value.to_le_bytes()));
+        //No attributes
+        stl.extend([0, 0]);
+    });
+    Ok(stl)
+}
+
+/// Writes a scene as a Wavefront OBJ for 3D printing, laid out like [`to_stl`].
+pub fn to_obj(scene: &Scene, options: &ExtrudeOptions) -> Result<String, String> {
+    let mesh = Mesh::extrude(scene, options)?;
+    let mut obj = String::new();
+
+    writeln!(obj, "# LSegui glyph, millimetres").unwrap();
+    writeln!(obj, "o glyph").unwrap();
+    mesh.vertices.iter().for_each(|vertex| {
+        let [x, y, z] = vertex.map(|value| num(value, DECIMALS));
+        writeln!(obj, "v {x} {y} {z}").unwrap();
+    });
+    //Vertices are counted from 1
+    mesh.triangles.iter().for_each(|[a, b, c]| {
+        writeln!(obj, "f {} {} {}", a + 1, b + 1, c + 1).unwrap();
+    });
+    Ok(obj)
+}
+
+//The outward facing unit normal of a triangle wound counter-clockwise
+fn normal([a, b, c]: &[[f32; 3]; 3]) -> [f32; 3] {
+    let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    let cross = [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ];
+    let length = cross.iter().map(|value| value * value).sum::<f32>().sqrt();
+    match length > 0.0 {
+        true => cross.map(|value| value / length),
+        false => [0.0; 3],
+    }
+}
+
+//Triangles wound counter-clockwise seen from outside, over shared vertices
+#[derive(Default)]
+struct Mesh {
+    vertices: Vec<[f32; 3]>,
+    triangles: Vec<[usize; 3]>,
+}
+
+impl Mesh {
+    fn extrude(scene: &Scene, options: &ExtrudeOptions) -> Result<Self, String> {
+        if options.width <= 0.0 {
+            return Err(String::from("the glyph has to be wider than 0 mm"));
+        }
+        if options.height <= 0.0 {
+            return Err(String::from("the strokes have to stand higher than 0 mm"));
+        }
+        if options.base != BaseShape::None && options.base_height <= 0.0 {
+            return Err(String::from("the base has to be thicker than 0 mm"));
+        }
+
+        //The word circles with their rings and dots, which the base is sized to
+        let circles = scene
+            .rings
+            .iter()
+            .map(|ring| (ring.center, ring.radius + ring.width / 2.0))
+            .chain(scene.nodes.iter().map(|node| (node.center, node.radius)))
+            .collect::<Vec<_>>();
+        let (min, max) = circles.iter().fold(
+            (
+                Point::new(f32::MAX, f32::MAX),
+                Point::new(f32::MIN, f32::MIN),
+            ),
+            |(min, max), (center, radius)| {
+                (
+                    Point::new(min.x.min(center.x - radius), min.y.min(center.y - radius)),
+                    Point::new(max.x.max(center.x + radius), max.y.max(center.y + radius)),
+                )
+            },
+        );
+        if max.x <= min.x {
+            return Err(String::from("the glyph has nothing to print"));
+        }
+        let scale = options.width / (max.x - min.x);
+        let middle = min.lerp(max, 0.5);
+        //Printers count y upwards, so the glyph isn't printed mirrored
+        let place =
+            |point: Point| Point::new((point.x - middle.x) * scale, (middle.y - point.y) * scale);
+        let tolerance = options.tolerance / scale;
+
+        let mut mesh = Mesh::default();
+        let base_height = match options.base {
+            BaseShape::None => 0.0,
+            _ => options.base_height,
+        };
+        let top = base_height + options.height;
+        match options.base {
+            BaseShape::None => {}
+            BaseShape::Disc => {
+                let radius = circles
+                    .iter()
+                    .map(|(center, radius)| center.distance(middle) + radius)
+                    .fold(0.0, f32::max);
+                let radius = radius * scale + options.base_margin;
+                mesh.disc(
+                    &flatten_circle(Point::default(), radius, options.tolerance),
+                    base_height,
+                );
+            }
+            BaseShape::Plate => {
+                let corner = place(max) - place(min);
+                let (x, y) = (
+                    corner.x.abs() / 2.0 + options.base_margin,
+                    corner.y.abs() / 2.0 + options.base_margin,
+                );
+                let corners = [(-x, -y), (x, -y), (x, y), (-x, y), (-x, -y)];
+                mesh.disc(&corners.map(|(x, y)| Point::new(x, y)), base_height);
+            }
+        }
+
+        scene.rings.iter().for_each(|ring| {
+            let line = ring
+                .flatten(tolerance)
+                .into_iter()
+                .map(place)
+                .collect::<Vec<_>>();
+            //Rings are offset away from their center, as they end where they start
+            let center = place(ring.center);
+            let half_width = ring.width * scale / 2.0;
+            let sides = line
+                .iter()
+                .map(|point| {
+                    let out = (*point - center).normalized() * half_width;
+                    (*point + out, *point - out)
+                })
+                .collect::<Vec<_>>();
+            mesh.strip(&sides[..sides.len() - 1], true, top);
+        });
+        scene.nodes.iter().for_each(|node| {
+            mesh.disc(
+                &flatten_circle(place(node.center), node.radius * scale, options.tolerance),
+                top,
+            );
+        });
+        scene.edges.iter().for_each(|edge| {
+            let line = edge
+                .path
+                .flatten(tolerance)
+                .into_iter()
+                .map(place)
+                .collect::<Vec<_>>();
+            let half_width = edge.width * scale / 2.0;
+            if let Some(sides) = sides(&line, half_width) {
+                mesh.strip(&sides, false, top);
+                //Round caps, as the edges are drawn with
+                [line[0], line[line.len() - 1]].iter().for_each(|end| {
+                    mesh.disc(&flatten_circle(*end, half_width, options.tolerance), top);
+                });
+            }
+        });
+        Ok(mesh)
+    }
+
+    //A band between pairs of points on its two sides, running round to its
+    //start again if it is closed
+    fn strip(&mut self, sides: &[(Point, Point)], closed: bool, top: f32) {
+        let count = sides.len();
+        let points = sides
+            .iter()
+            .map(|(left, _)| *left)
+            .chain(sides.iter().map(|(_, right)| *right))
+            .collect::<Vec<_>>();
+        let steps = match closed {
+            true => count,
+            false => count - 1,
+        };
+        let (mut caps, mut walls) = (vec![], vec![]);
+        (0..steps).for_each(|i| {
+            let j = (i + 1) % count;
+            let (left, right, next_left, next_right) = (i, count + i, j, count + j);
+            caps.push([left, right, next_right]);
+            caps.push([left, next_right, next_left]);
+            walls.push((right, next_right));
+            walls.push((next_left, left));
+        });
+        if !closed {
+            walls.push((0, count));
+            walls.push((count + count - 1, count - 1));
+        }
+        self.solid(&points, &caps, &walls, top);
+    }
+
+    //A flat polygon, given with its first point repeated at the end, fanned
+    //out from its middle
+    fn disc(&mut self, outline: &[Point], top: f32) {
+        let count = outline.len() - 1;
+        let middle = outline[..count]
+            .iter()
+            .fold(Point::default(), |sum, point| sum + *point)
+            * (1.0 / count as f32);
+        let points = outline[..count]
+            .iter()
+            .copied()
+            .chain([middle])
+            .collect::<Vec<_>>();
+        let caps = (0..count)
+            .map(|i| [count, i, (i + 1) % count])
+            .collect::<Vec<_>>();
+        let walls = (0..count).map(|i| (i, (i + 1) % count)).collect::<Vec<_>>();
+        self.solid(&points, &caps, &walls, top);
+    }
+
+    //Raises flat triangles from the bed to `top`, closed by walls along the
+    //edges of the triangles that no other one shares. The triangles and the
+    //walls run the same way round, whichever way that is.
+    fn solid(&mut self, points: &[Point], caps: &[[usize; 3]], walls: &[(usize, usize)], top: f32) {
+        let area = caps
+            .iter()
+            .map(|[a, b, c]| {
+                let (u, v) = (points[*b] - points[*a], points[*c] - points[*a]);
+                u.x * v.y - u.y * v.x
+            })
+            .sum::<f32>();
+        let flipped = area < 0.0;
+
+        let first = self.vertices.len();
+        self.vertices
+            .extend(points.iter().map(|point| [point.x, point.y, 0.0]));
+        self.vertices
+            .extend(points.iter().map(|point| [point.x, point.y, top]));
+        let (bottom, top) = (first, first + points.len());
+
+        caps.iter().for_each(|[a, b, c]| {
+            let (b, c) = match flipped {
+                true => (c, b),
+                false => (b, c),
+            };
+            self.triangles.push([top + a, top + b, top + c]);
+            self.triangles.push([bottom + a, bottom + c, bottom + b]);
+        });
+        walls.iter().for_each(|(from, to)| {
+            let (from, to) = match flipped {
+                true => (to, from),
+                false => (from, to),
+            };
+            self.triangles.push([bottom + from, bottom + to, top + to]);
+            self.triangles.push([bottom + from, top + to, top + from]);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{Glyph, GlyphStyle};
+
+    fn scene() -> Scene {
+        Scene::new(&Glyph::new("hello world").unwrap(), &GlyphStyle::default())
+    }
+
+    //Every edge is run along once each way, by the triangles either side of it
+    //wound the same way round, and every solid encloses a positive volume
+    fn assert_closed(mesh: &Mesh) {
+        let mut edges = HashMap::new();
+        mesh.triangles.iter().for_each(|[a, b, c]| {
+            [(a, b), (b, c), (c, a)].into_iter().for_each(|(from, to)| {
+                *edges.entry((*from, *to)).or_insert(0) += 1;
+            });
+        });
+        edges.iter().for_each(|((from, to), count)| {
+            assert_eq!(*count, 1, "{from} to {to}");
+            assert_eq!(edges.get(&(*to, *from)), Some(&1), "{from} to {to}");
+        });
+
+        //The solids share no vertices, so they are told apart by the vertices their triangles join
+        let mut solid = (0..mesh.vertices.len()).collect::<Vec<_>>();
+        fn root(solid: &mut [usize], vertex: usize) -> usize {
+            let mut vertex = vertex;
+            while solid[vertex] != vertex {
+                vertex = solid[vertex];
+            }
+            vertex
+        }
+        mesh.triangles.iter().for_each(|[a, b, c]| {
+            let a = root(&mut solid, *a);
+            [b, c].into_iter().for_each(|other| {
+                let other = root(&mut solid, *other);
+                solid[other] = a;
+            });
+        });
+        let mut volumes = HashMap::new();
+        mesh.triangles.iter().for_each(|triangle| {
+            let [a, b, c] = triangle.map(|vertex| mesh.vertices[vertex]);
+            let volume = (a[0] * (b[1] * c[2] - b[2] * c[1]) - a[1] * (b[0] * c[2] - b[2] * c[0])
+                + a[2] * (b[0] * c[1] - b[1] * c[0]))
+                / 6.0;
+            *volumes.entry(root(&mut solid, triangle[0])).or_insert(0.0) += volume;
+        });
+        volumes
+            .values()
+            .for_each(|volume| assert!(*volume > 0.0, "a solid is inside out"));
+    }
+
+    #[test]
+    fn stl_triangles_match_the_header() {
+        let stl = to_stl(&scene(), &ExtrudeOptions::default()).unwrap();
+        let count = u32::from_le_bytes(stl[80..84].try_into().unwrap()) as usize;
+        assert!(count > 0);
+        assert_eq!(stl.len(), 84 + count * 50);
+
+        let obj = to_obj(&scene(), &ExtrudeOptions::default()).unwrap();
+        assert_eq!(
+            obj.lines().filter(|line| line.starts_with("f ")).count(),
+            count
+        );
+    }
+
+    #[test]
+    fn every_base_gives_closed_solids() {
+        let scene = scene();
+        BaseShape::ALL.into_iter().for_each(|base| {
+            let options = ExtrudeOptions {
+                base,
+                ..ExtrudeOptions::default()
+            };
+            let mesh = Mesh::extrude(&scene, &options).unwrap();
+            assert_closed(&mesh);
+
+            let top = mesh.vertices.iter().map(|[_, _, z]| *z).fold(0.0, f32::max);
+            let expected = match base {
+                BaseShape::None => options.height,
+                _ => options.base_height + options.height,
+            };
+            assert!((top - expected).abs() < 1e-4, "{}", base.name());
+            //The glyph comes out as wide as it was asked for, the base reaches beyond it
+            let width = mesh
+                .vertices
+                .iter()
+                .map(|[x, _, _]| x.abs())
+                .fold(0.0, f32::max)
+                * 2.0;
+            assert!(width >= options.width - 0.01, "{}", base.name());
+        });
+    }
+
+    #[test]
+    fn normals_point_out() {
+        let up = normal(&[[0.0, 0.0, 1.0], [1.0, 0.0, 1.0], [0.0, 1.0, 1.0]]);
+        assert_eq!(up, [0.0, 0.0, 1.0]);
+    }
+}
@@ -0,0 +1,13 @@
+//Numbers as they are written into the text formats, with at most `decimals`
+//decimals and no trailing zeros
+pub(crate) fn num(value: f32, decimals: usize) -> String {
+    let text = format!("{value:.decimals$}");
+    let text = match decimals {
+        0 => text.as_str(),
+        _ => text.trim_end_matches('0').trim_end_matches('.'),
+    };
+    match text {
+        "-0" | "" => String::from("0"),
+        _ => text.to_string(),
+    }
+}
@@ -0,0 +1,228 @@
+use petgraph::stable_graph::{NodeIndex, StableGraph};
+
+use crate::{EdgeKind, GlyphError};
+
+#[derive(Clone, Debug)]
+pub struct Word {
+    pub word: String,
+    pub nodes: Vec<NodeIndex<u32>>,
+    pub layout_top: NodeLayout,
+    pub layout_bottom: NodeLayout,
+}
+
+impl Default for Word {
+    fn default() -> Self {
+        Self {
+            word: String::new(),
+            nodes: vec![],
+            layout_top: Alone,
+            layout_bottom: Alone,
+        }
+    }
+}
+
+impl Word {
+    pub fn new(word: String, nodes: Vec<NodeIndex<u32>>) -> Self {
+        Self {
+            word,
+            nodes,
+            layout_top: Alone,
+            layout_bottom: Alone,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Phrase {
+    //phrase: Vec<String>,
+    pub phrase_words: Vec<Word>,
+    pub graph: StableGraph<(), EdgeKind>,
+}
+
+impl Phrase {
+    /// Splits `phrase` into words of upper case letters, dropping everything
+    /// else. A word that ends with the letter it starts with is drawn without
+    /// that last letter.
+    pub fn new(phrase: &str) -> Result<Self, GlyphError> {
+        let mut g: StableGraph<(), EdgeKind> = StableGraph::new();
+
+        let phrase = split_words(phrase)?;
+        let mut phrase_words = vec![];
+        phrase.clone().iter().for_each(|word| {
+            let mut node_indices: Vec<NodeIndex<u32>> = vec![];
+            word.chars().for_each(|_char| {
+                node_indices.push(g.add_node(()));
+            });
+
+            let word = Word::new(word.to_string(), node_indices);
+
+            phrase_words.push(word);
+        });
+
+        Ok(Self {
+            //phrase,
+            phrase_words,
+            graph: g,
+        })
+    }
+    pub fn analyse_phrase(&mut self) {
+        //Analyse the phrase, given N words, where 1 is the first word and N is the last word
+        //for each word in the phrase:
+        //  find a letter shared between each subsequent word, so that word 1 shares a letter with word 2, word 2 shares a letter with word 3, etc.
+        //  if there is no letter shared between the current word and the next word
+        //      then shift the nodes of each of the two words away from the border between the two words
+        //      so that the nodes of the current word are shifted away from the bottom most point of the circle of the current word
+        //      and the nodes of the next word are shifted away from the top most point of the circle of the next word
+        //      node shift should shift the nodes as little distance as possible
+
+        //Start over, the words may have changed since the phrase was last analysed
+        self.phrase_words.iter_mut().for_each(|word| {
+            word.layout_top = Alone;
+            word.layout_bottom = Alone;
+        });
+
+        let chars = self
+            .phrase_words
+            .iter()
+            .map(|word| word.word.chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        (1..self.phrase_words.len()).for_each(|next| {
+            let current_chars = &chars[next - 1];
+            let next_chars = &chars[next];
+            //A letter already joined to the previous word can't also be joined to the next one
+            let taken = match self.phrase_words[next - 1].layout_top {
+                SameCharTop(i) => Some(i),
+                _ => None,
+            };
+            let boundaries = [
+                //The last letter of the current word is the first letter of the next word
+                (current_chars.len().checked_sub(1), Some(0)),
+                //The first letter of the current word is the last letter of the next word
+                (Some(0), next_chars.len().checked_sub(1)),
+            ];
+            let shared = boundaries.into_iter().find_map(|boundary| match boundary {
+                (Some(ci), Some(ni))
+                    if Some(ci) != taken && current_chars.get(ci) == next_chars.get(ni) =>
+                {
+                    Some((ci, ni))
+                }
+                _ => None,
+            });
+            match shared {
+                Some((ci, ni)) => {
+                    self.phrase_words[next - 1].layout_bottom = SameCharBottom(ci);
+                    self.phrase_words[next].layout_top = SameCharTop(ni);
+                }
+                None => {
+                    self.phrase_words[next - 1].layout_bottom = RepelBottom;
+                    self.phrase_words[next].layout_top = RepelTop;
+                }
+            }
+        });
+    }
+}
+
+//The words of a phrase as Phrase::new draws them
+pub(crate) fn split_words(phrase: &str) -> Result<Vec<String>, GlyphError> {
+    let words = phrase
+        .chars()
+        .filter(|c| c.is_ascii_alphabetic() || c.is_whitespace())
+        .collect::<String>()
+        .to_uppercase()
+        .split_whitespace()
+        .map(|word| {
+            //A single letter is both the first and the last, and must stay
+            if word.len() > 1 && word.chars().next() == word.chars().last() {
+                let new_word = word.chars().take(word.len() - 1).collect::<String>();
+                new_word
+            } else {
+                word.to_string()
+            }
+        })
+        .collect::<Vec<_>>();
+    if words.is_empty() {
+        return Err(GlyphError::EmptyPhrase);
+    }
+    Ok(words)
+}
+
+/// How a word's nodes are placed around the points where its circle touches
+/// the previous word (top) and the next word (bottom).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NodeLayout {
+    /// No shared letter with the previous word, nodes keep clear of the top.
+    RepelTop,
+    /// No shared letter with the next word, nodes keep clear of the bottom.
+    RepelBottom,
+    /// The letter at this index is the node joined to the previous word.
+    SameCharTop(usize),
+    /// The letter at this index is the node joined to the next word.
+    SameCharBottom(usize),
+    /// There is no word on this side.
+    Alone,
+}
+
+use NodeLayout::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_are_joined_by_the_letters_at_their_ends() {
+        //Every word's top and bottom layout, for phrases covering each way words can meet
+        let cases: &[(&str, &[(NodeLayout, NodeLayout)])] = &[
+            ("a", &[(Alone, Alone)]),
+            //The last letter of a word starts the next
+            (
+                "hello orbit",
+                &[(Alone, SameCharBottom(4)), (SameCharTop(0), Alone)],
+            ),
+            //The first letter of a word ends the next
+            (
+                "tan sat",
+                &[(Alone, SameCharBottom(0)), (SameCharTop(2), Alone)],
+            ),
+            ("cat dog", &[(Alone, RepelBottom), (RepelTop, Alone)]),
+            //A single letter is both ends of its word
+            (
+                "a art tea",
+                &[
+                    (Alone, SameCharBottom(0)),
+                    (SameCharTop(0), SameCharBottom(2)),
+                    (SameCharTop(0), Alone),
+                ],
+            ),
+            //A single letter joined to the word before has none left for the word after
+            (
+                "ba a ab",
+                &[
+                    (Alone, SameCharBottom(1)),
+                    (SameCharTop(0), RepelBottom),
+                    (RepelTop, Alone),
+                ],
+            ),
+        ];
+
+        cases.iter().for_each(|(text, expected)| {
+            let mut phrase = Phrase::new(text).unwrap();
+            phrase.analyse_phrase();
+            let layouts = phrase
+                .phrase_words
+                .iter()
+                .map(|word| (word.layout_top, word.layout_bottom))
+                .collect::<Vec<_>>();
+            assert_eq!(layouts, *expected, "{text}");
+        });
+    }
+
+    #[test]
+    fn analysing_again_starts_over() {
+        let mut phrase = Phrase::new("hello orbit").unwrap();
+        phrase.analyse_phrase();
+        phrase.phrase_words[1].word = String::from("CAT");
+        phrase.analyse_phrase();
+        assert_eq!(phrase.phrase_words[0].layout_bottom, RepelBottom);
+        assert_eq!(phrase.phrase_words[1].layout_top, RepelTop);
+    }
+}
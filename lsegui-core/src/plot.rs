@@ -0,0 +1,332 @@
+use std::fmt::Write;
+
+use crate::number::num;
+use crate::{Point, Scene};
+
+//Decimals of a millimetre the pen moves are written with
+const DECIMALS: usize = 3;
+
+/// Paper, margins and speeds for pen plotters. Lengths are in millimetres
+/// and speeds in millimetres per minute.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PlotOptions {
+    pub paper_width: f32,
+    pub paper_height: f32,
+    /// Space left free on every side of the paper.
+    pub margin: f32,
+    /// How fast the pen moves while it draws.
+    pub feed_rate: f32,
+    /// How fast the pen moves while it is lifted, for G-code.
+    pub travel_rate: f32,
+    /// How high the pen is lifted between strokes, for G-code.
+    pub pen_up: f32,
+    /// How far the straight steps of a curve may stray from it.
+    pub tolerance: f32,
+}
+
+impl Default for PlotOptions {
+    //A4 portrait at a speed most plotters draw cleanly at
+    fn default() -> Self {
+        PlotOptions {
+            paper_width: 210.0,
+            paper_height: 297.0,
+            margin: 15.0,
+            feed_rate: 1500.0,
+            travel_rate: 3000.0,
+            pen_up: 5.0,
+            tolerance: 0.1,
+        }
+    }
+}
+
+impl PlotOptions {
+    /// Paper sizes in millimetres accepted by [`PlotOptions::paper_named`].
+    pub const PAPERS: &'static [(&'static str, f32, f32)] = &[
+        ("a5", 148.0, 210.0),
+        ("a4", 210.0, 297.0),
+        ("a3", 297.0, 420.0),
+        ("a2", 420.0, 594.0),
+        ("letter", 215.9, 279.4),
+        ("tabloid", 279.4, 431.8),
+    ];
+
+    /// The width and height of a named portrait paper size.
+    pub fn paper_named(name: &str) -> Option<(f32, f32)> {
+        let name = name.to_ascii_lowercase();
+        PlotOptions::PAPERS
+            .iter()
+            .find(|(paper, _, _)| *paper == name)
+            .map(|(_, width, height)| (*width, *height))
+    }
+}
+
+//HPGL plotter units in a millimetre
+const HPGL_UNITS: f32 = 40.0;
+
+/// The strokes a pen plotter draws for a scene, in the order it draws them.
+///
+/// The scene is scaled to fit inside the margins and centered on the paper,
+/// with positions in millimetres from the bottom left corner. Rings and edges
+/// are drawn, nodes and labels are left out. Strokes are ordered and turned
+/// around so the pen travels as little as it can between them.
+pub fn plot_paths(scene: &Scene, options: &PlotOptions) -> Result<Vec<Vec<Point>>, String> {
+    let room_x = options.paper_width - 2.0 * options.margin;
+    let room_y = options.paper_height - 2.0 * options.margin;
+    if !(room_x > 0.0 && room_y > 0.0) {
+        return Err(format!(
+            "a {} mm margin leaves no room on {} x {} mm paper",
+            options.margin, options.paper_width, options.paper_height
+        ));
+    }
+    if !(options.feed_rate > 0.0 && options.travel_rate > 0.0) {
+        return Err(String::from("the plotter speeds have to be above 0"));
+    }
+
+    let bounds = scene.bounds;
+    let scale = (room_x / bounds.width()).min(room_y / bounds.height());
+    let center = bounds.center();
+    //Plotters count y upwards from the bottom of the paper
+    let to_paper = |point: Point| {
+        Point::new(
+            options.paper_width / 2.0 + (point.x - center.x) * scale,
+            options.paper_height / 2.0 - (point.y - center.y) * scale,
+        )
+    };
+    let tolerance = options.tolerance / scale;
+
+    let strokes = scene
+        .rings
+        .iter()
+        .map(|ring| ring.flatten(tolerance))
+        .chain(scene.edges.iter().map(|edge| edge.path.flatten(tolerance)))
+        .map(|stroke| stroke.into_iter().map(to_paper).collect::<Vec<_>>())
+        .filter(|stroke| stroke.len() > 1)
+        .collect();
+    Ok(order_strokes(strokes))
+}
+
+/// Writes a scene as G-code for a pen plotter that lifts its pen along Z.
+pub fn to_gcode(scene: &Scene, options: &PlotOptions) -> Result<String, String> {
+    let strokes = plot_paths(scene, options)?;
+    let mut gcode = String::new();
+
+    writeln!(
+        gcode,
+        "; LSegui glyph on {} x {} mm paper",
+        num(options.paper_width, DECIMALS),
+        num(options.paper_height, DECIMALS)
+    )
+    .unwrap();
+    writeln!(gcode, "G21 ; millimetres").unwrap();
+    writeln!(gcode, "G90 ; absolute positions").unwrap();
+    let pen_up = format!("G0 Z{}", num(options.pen_up, DECIMALS));
+    writeln!(gcode, "{pen_up}").unwrap();
+
+    strokes.iter().for_each(|stroke| {
+        let start = stroke[0];
+        writeln!(
+            gcode,
+            "G0 X{} Y{} F{}",
+            num(start.x, DECIMALS),
+            num(start.y, DECIMALS),
+            num(options.travel_rate, DECIMALS)
+        )
+        .unwrap();
+        writeln!(gcode, "G1 Z0 F{}", num(options.feed_rate, DECIMALS)).unwrap();
+        stroke[1..].iter().for_each(|point| {
+            writeln!(
+                gcode,
+                "G1 X{} Y{}",
+                num(point.x, DECIMALS),
+                num(point.y, DECIMALS)
+            )
+            .unwrap();
+        });
+        writeln!(gcode, "{pen_up}").unwrap();
+    });
+
+    writeln!(gcode, "G0 X0 Y0 F{}", num(options.travel_rate, DECIMALS)).unwrap();
+    writeln!(gcode, "M2").unwrap();
+    Ok(gcode)
+}
+
+/// Writes a scene as HPGL, drawn with the first pen.
+pub fn to_hpgl(scene: &Scene, options: &PlotOptions) -> Result<String, String> {
+    let strokes = plot_paths(scene, options)?;
+    let unit = |value: f32| (value * HPGL_UNITS).round() as i32;
+    let mut hpgl = String::new();
+
+    //Velocity is given in centimetres per second
+    writeln!(
+        hpgl,
+        "IN;SP1;VS{};",
+        num(options.feed_rate / 600.0, DECIMALS)
+    )
+    .unwrap();
+    strokes.iter().for_each(|stroke| {
+        let start = stroke[0];
+        let drawn = stroke[1..]
+            .iter()
+            .map(|point| format!("{},{}", unit(point.x), unit(point.y)))
+            .collect::<Vec<_>>()
+            .join(",");
+        writeln!(hpgl, "PU{},{};PD{drawn};", unit(start.x), unit(start.y)).unwrap();
+    });
+    writeln!(hpgl, "PU0,0;SP0;").unwrap();
+    Ok(hpgl)
+}
+
+//Draws every stroke next from wherever the pen is closest to, starting at the origin.
+//Closed strokes can start at any of their points, open ones at either end.
+fn order_strokes(mut strokes: Vec<Vec<Point>>) -> Vec<Vec<Point>> {
+    let mut ordered = Vec::with_capacity(strokes.len());
+    let mut pen = Point::default();
+
+    while !strokes.is_empty() {
+        let (i, (start, _)) = strokes
+            .iter()
+            .map(|stroke| closest_start(stroke, pen))
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.1.total_cmp(&b.1))
+            .expect("there are strokes left");
+
+        let mut stroke = strokes.swap_remove(i);
+        if is_closed(&stroke) {
+            stroke.pop();
+            stroke.rotate_left(start);
+            stroke.push(stroke[0]);
+        } else if start > 0 {
+            stroke.reverse();
+        }
+        pen = *stroke.last().expect("strokes have points");
+        ordered.push(stroke);
+    }
+    ordered
+}
+
+//Where a stroke would best start from the pen, and how far the pen travels to it
+fn closest_start(stroke: &[Point], pen: Point) -> (usize, f32) {
+    let last = stroke.len() - 1;
+    let starts = match is_closed(stroke) {
+        true => (0..last).collect::<Vec<_>>(),
+        false => vec![0, last],
+    };
+    starts
+        .into_iter()
+        .map(|i| (i, stroke[i].distance(pen)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap_or((0, 0.0))
+}
+
+//Rings end on the very point they start at
+fn is_closed(stroke: &[Point]) -> bool {
+    stroke.len() > 2 && stroke.first() == stroke.last()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Glyph, GlyphStyle};
+
+    fn scene() -> Scene {
+        Scene::new(&Glyph::new("hello world").unwrap(), &GlyphStyle::default())
+    }
+
+    //How far the pen moves lifted, from the origin through every stroke in turn
+    fn travel(strokes: &[Vec<Point>]) -> f32 {
+        let mut pen = Point::default();
+        strokes
+            .iter()
+            .map(|stroke| {
+                let moved = stroke[0].distance(pen);
+                pen = *stroke.last().unwrap();
+                moved
+            })
+            .sum()
+    }
+
+    #[test]
+    fn ordering_travels_no_further_than_drawn_order() {
+        let line = |from: (f32, f32), to: (f32, f32)| {
+            vec![Point::new(from.0, from.1), Point::new(to.0, to.1)]
+        };
+        let square = [
+            (50.0, 0.0),
+            (60.0, 0.0),
+            (60.0, 10.0),
+            (50.0, 10.0),
+            (50.0, 0.0),
+        ]
+        .map(|(x, y)| Point::new(x, y))
+        .to_vec();
+        let strokes = vec![
+            line((40.0, 0.0), (30.0, 0.0)),
+            square,
+            line((0.0, 0.0), (10.0, 0.0)),
+            line((20.0, 0.0), (10.0, 0.0)),
+        ];
+
+        let ordered = order_strokes(strokes.clone());
+        assert_eq!(ordered.len(), strokes.len());
+        assert!(travel(&ordered) <= travel(&strokes));
+        //The lines join up end to end and only the gap between them is travelled
+        assert_eq!(travel(&ordered), 20.0);
+        assert!(is_closed(&ordered[3]) && ordered[3][0] == Point::new(50.0, 0.0));
+    }
+
+    #[test]
+    fn gcode_stays_inside_the_margins() {
+        let scene = scene();
+        [PlotOptions::default(), {
+            let (paper_width, paper_height) = PlotOptions::paper_named("Letter").unwrap();
+            PlotOptions {
+                paper_width,
+                paper_height,
+                margin: 40.0,
+                ..PlotOptions::default()
+            }
+        }]
+        .into_iter()
+        .for_each(|options| {
+            let gcode = to_gcode(&scene, &options).unwrap();
+            let moves = gcode
+                .lines()
+                .filter(|line| line.starts_with("G0 X") || line.starts_with("G1 X"))
+                .filter(|line| !line.starts_with("G0 X0 Y0"))
+                .map(|line| {
+                    let mut words = line.split(' ').skip(1);
+                    let mut value = |axis: char| {
+                        let word = words.next().unwrap();
+                        assert!(word.starts_with(axis), "{line}");
+                        word[1..].parse::<f32>().unwrap()
+                    };
+                    (value('X'), value('Y'))
+                })
+                .collect::<Vec<_>>();
+            assert!(moves.len() > 10);
+
+            let slack = 0.01;
+            moves.iter().for_each(|(x, y)| {
+                assert!(*x >= options.margin - slack, "{x}");
+                assert!(*x <= options.paper_width - options.margin + slack, "{x}");
+                assert!(*y >= options.margin - slack, "{y}");
+                assert!(*y <= options.paper_height - options.margin + slack, "{y}");
+            });
+        });
+    }
+
+    #[test]
+    fn hpgl_speed_is_in_centimetres_per_second() {
+        let scene = scene();
+        [(1500.0, "VS2.5;"), (600.0, "VS1;"), (900.0, "VS1.5;")]
+            .into_iter()
+            .for_each(|(feed_rate, speed)| {
+                let options = PlotOptions {
+                    feed_rate,
+                    ..PlotOptions::default()
+                };
+                let hpgl = to_hpgl(&scene, &options).unwrap();
+                assert!(hpgl.starts_with(&format!("IN;SP1;{speed}")), "{hpgl}");
+            });
+    }
+}
@@ -0,0 +1,142 @@
+use tiny_skia::{FillRule, LineCap, Paint, PathBuilder, Pixmap, Stroke, Transform};
+
+use crate::letters::{label_lines, label_width};
+use crate::{Color, EdgePath, Scene};
+
+/// Size and background of a rasterized glyph.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RasterOptions {
+    pub width: u32,
+    pub height: u32,
+    /// Leave the background see-through instead of filling it with the
+    /// scene's background colour.
+    pub transparent: bool,
+}
+
+impl Default for RasterOptions {
+    fn default() -> Self {
+        RasterOptions {
+            width: 2048,
+            height: 2048,
+            transparent: false,
+        }
+    }
+}
+
+/// Draws a scene into a PNG on the CPU, scaled to fit the requested size.
+///
+/// Labels are written in a plain font of single strokes, whichever
+/// [`LabelFont`](crate::LabelFont) the scene asks for.
+pub fn to_png(scene: &Scene, options: &RasterOptions) -> Result<Vec<u8>, String> {
+    let mut pixmap = Pixmap::new(options.width, options.height).ok_or_else(|| {
+        format!(
+            "{}x{} is not a valid image size",
+            options.width, options.height
+        )
+    })?;
+
+    if !options.transparent {
+        pixmap.fill(skia_color(scene.background));
+    }
+
+    //Fit the scene into the image, centered and keeping its aspect ratio
+    let bounds = scene.bounds;
+    let scale =
+        (options.width as f32 / bounds.width()).min(options.height as f32 / bounds.height());
+    let center = bounds.center();
+    let transform = Transform::from_row(
+        scale,
+        0.0,
+        0.0,
+        scale,
+        options.width as f32 / 2.0 - center.x * scale,
+        options.height as f32 / 2.0 - center.y * scale,
+    );
+
+    scene.rings.iter().for_each(|ring| {
+        if let Some(path) = PathBuilder::from_circle(ring.center.x, ring.center.y, ring.radius) {
+            pixmap.stroke_path(
+                &path,
+                &paint(ring.color),
+                &stroke(ring.width),
+                transform,
+                None,
+            );
+        }
+    });
+
+    scene.edges.iter().for_each(|edge| {
+        let mut builder = PathBuilder::new();
+        match edge.path {
+            EdgePath::Quadratic([start, control, end]) => {
+                builder.move_to(start.x, start.y);
+                builder.quad_to(control.x, control.y, end.x, end.y);
+            }
+            EdgePath::Cubic([start, control1, control2, end]) => {
+                builder.move_to(start.x, start.y);
+                builder.cubic_to(control1.x, control1.y, control2.x, control2.y, end.x, end.y);
+            }
+        }
+        if let Some(path) = builder.finish() {
+            pixmap.stroke_path(
+                &path,
+                &paint(edge.color),
+                &stroke(edge.width),
+                transform,
+                None,
+            );
+        }
+    });
+
+    scene.nodes.iter().for_each(|node| {
+        if let Some(path) = PathBuilder::from_circle(node.center.x, node.center.y, node.radius) {
+            pixmap.fill_path(
+                &path,
+                &paint(node.color),
+                FillRule::Winding,
+                transform,
+                None,
+            );
+        }
+    });
+
+    scene.labels.iter().for_each(|label| {
+        let mut builder = PathBuilder::new();
+        label_lines(label).iter().for_each(|line| {
+            builder.move_to(line[0].x, line[0].y);
+            line[1..]
+                .iter()
+                .for_each(|point| builder.line_to(point.x, point.y));
+        });
+        if let Some(path) = builder.finish() {
+            pixmap.stroke_path(
+                &path,
+                &paint(label.color),
+                &stroke(label_width(label)),
+                transform,
+                None,
+            );
+        }
+    });
+
+    pixmap.encode_png().map_err(|error| error.to_string())
+}
+
+fn skia_color(color: Color) -> tiny_skia::Color {
+    tiny_skia::Color::from_rgba8(color.r, color.g, color.b, color.a)
+}
+
+fn paint(color: Color) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color(skia_color(color));
+    paint.anti_alias = true;
+    paint
+}
+
+fn stroke(width: f32) -> Stroke {
+    Stroke {
+        width,
+        line_cap: LineCap::Round,
+        ..Stroke::default()
+    }
+}
@@ -0,0 +1,380 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::circle_layout::MOVED_BY;
+use crate::{
+    Color, ColorScheme, ConnectionTable, EdgeStyle, Glyph, GlyphError, History, Point, RuleError,
+    Theme, DEFAULT_PALETTE,
+};
+
+//Bumped whenever a change to the format would stop older versions reading it correctly
+const PROJECT_VERSION: u32 = 1;
+
+/// Everything needed to reopen a glyph exactly as it was left, including
+/// nodes that were moved by hand.
+///
+/// Saved as JSON, usually with the `.lsegui` extension.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Project {
+    pub version: u32,
+    /// The phrase as it was typed.
+    pub phrase: String,
+    pub rules_name: String,
+    /// The connection table, in the same format as a rules file.
+    pub rules: String,
+    /// Name of the theme the glyph was drawn with.
+    pub theme: String,
+    /// The whole theme when it isn't a built in one, edited or loaded from a
+    /// file. Older versions of LSegui skip it and only look the name up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_theme: Option<Theme>,
+    pub circles: Vec<CircleState>,
+    /// How edges are drawn unless they have a style of their own.
+    #[serde(default)]
+    pub edge_style: EdgeStyle,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub edge_styles: Vec<EdgeStyleState>,
+    #[serde(default)]
+    pub color_scheme: ColorScheme,
+    /// The colours the scheme picks from.
+    #[serde(default = "default_palette")]
+    pub palette: Vec<Color>,
+    /// Edits that can still be undone and redone. Older versions of LSegui
+    /// skip it, so it doesn't need a new format version.
+    #[serde(default, skip_serializing_if = "History::is_empty")]
+    pub history: History,
+}
+
+/// Where a word circle and its letter nodes were placed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CircleState {
+    pub word: String,
+    pub center: Point,
+    pub radius: f32,
+    pub rotation: f32,
+    pub origin: Point,
+    /// Moved or turned by hand rather than where the layout put it.
+    #[serde(default)]
+    pub pinned: bool,
+    pub nodes: Vec<NodeState>,
+}
+
+/// Where a letter node was placed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NodeState {
+    pub letter: char,
+    pub pos: Point,
+    pub angle: f32,
+}
+
+/// An edge drawn in a style of its own. Nodes are given by the position of
+/// their letter in the whole phrase, as node indices aren't kept.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EdgeStyleState {
+    pub source: usize,
+    pub target: usize,
+    pub order: usize,
+    pub style: EdgeStyle,
+}
+
+/// Why a project couldn't be read back.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProjectError {
+    /// The file isn't a project, or is from a newer version of LSegui.
+    Format(String),
+    /// The connection table saved in the project doesn't parse.
+    Rules(Vec<RuleError>),
+    /// The phrase couldn't be turned into a glyph.
+    Glyph(GlyphError),
+    /// The saved circles don't match the words of the phrase.
+    Mismatch,
+}
+
+impl fmt::Display for ProjectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProjectError::Format(message) => write!(f, "not a readable project: {message}"),
+            ProjectError::Rules(errors) => {
+                write!(f, "the saved connection table has errors")?;
+                errors.iter().try_for_each(|error| write!(f, "\n  {error}"))
+            }
+            ProjectError::Glyph(error) => error.fmt(f),
+            ProjectError::Mismatch => write!(f, "the saved circles don't match the phrase"),
+        }
+    }
+}
+
+impl std::error::Error for ProjectError {}
+
+impl Project {
+    /// Captures `glyph` as built from `phrase` with `rules`.
+    pub fn new(
+        phrase: &str,
+        glyph: &Glyph,
+        rules_name: &str,
+        rules: &ConnectionTable,
+        theme: &Theme,
+    ) -> Self {
+        let circles = glyph
+            .circles
+            .circle_list
+            .iter()
+            .map(|circle| CircleState {
+                word: circle.word.word.clone(),
+                center: circle.center,
+                radius: circle.radius,
+                rotation: circle.rotation,
+                origin: circle.origin,
+                pinned: circle.pinned,
+                nodes: circle
+                    .word
+                    .word
+                    .chars()
+                    .zip(circle.node_pos_list.node_pos_list.iter())
+                    .map(|(letter, node_pos)| NodeState {
+                        letter,
+                        pos: node_pos.pos,
+                        angle: node_pos.angle,
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let letters = glyph
+            .nodes()
+            .enumerate()
+            .map(|(i, (node, _, _))| (node, i))
+            .collect::<HashMap<_, _>>();
+        let mut edge_styles = glyph
+            .edge_styles
+            .iter()
+            .filter_map(|((source, target, order), style)| {
+                Some(EdgeStyleState {
+                    source: *letters.get(source)?,
+                    target: *letters.get(target)?,
+                    order: *order,
+                    style: *style,
+                })
+            })
+            .collect::<Vec<_>>();
+        //Keeps saved files and undo snapshots comparable
+        edge_styles.sort_by_key(|edge| (edge.source, edge.target, edge.order));
+
+        Project {
+            version: PROJECT_VERSION,
+            phrase: phrase.to_string(),
+            rules_name: rules_name.to_string(),
+            rules: rules.to_toml(),
+            theme: theme.name.clone(),
+            custom_theme: (Theme::named(&theme.name).as_ref() != Some(theme))
+                .then(|| theme.clone()),
+            circles,
+            edge_style: EdgeStyle::default(),
+            edge_styles,
+            color_scheme: ColorScheme::default(),
+            palette: default_palette(),
+            history: History::default(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("projects should always serialize")
+    }
+
+    pub fn from_json(source: &str) -> Result<Self, ProjectError> {
+        let project: Project = serde_json::from_str(source)
+            .map_err(|error| ProjectError::Format(error.to_string()))?;
+        if project.version > PROJECT_VERSION {
+            return Err(ProjectError::Format(format!(
+                "saved by a newer version of LSegui (format {})",
+                project.version
+            )));
+        }
+        Ok(project)
+    }
+
+    /// The theme the glyph was drawn with, if it's built in or was saved whole.
+    pub fn saved_theme(&self) -> Option<Theme> {
+        self.custom_theme
+            .clone()
+            .or_else(|| Theme::named(&self.theme))
+    }
+
+    /// The connection table saved in the project.
+    pub fn connection_table(&self) -> Result<ConnectionTable, ProjectError> {
+        ConnectionTable::parse(&self.rules).map_err(ProjectError::Rules)
+    }
+
+    /// Rebuilds the glyph and puts every circle and node back where it was saved.
+    pub fn glyph(&self) -> Result<Glyph, ProjectError> {
+        let rules = self.connection_table()?;
+        let mut glyph = Glyph::with_rules(&self.phrase, &rules).map_err(ProjectError::Glyph)?;
+
+        let circles = &mut glyph.circles.circle_list;
+        let matches = circles.len() == self.circles.len()
+            && circles
+                .iter()
+                .zip(&self.circles)
+                .all(|(circle, saved)| circle.word.word == saved.word);
+        if !matches {
+            return Err(ProjectError::Mismatch);
+        }
+
+        circles
+            .iter_mut()
+            .zip(&self.circles)
+            .for_each(|(circle, saved)| {
+                //Compared on the circle, so nodes that only went along with a pinned circle aren't moved
+                let moved = circle
+                    .node_pos_list
+                    .node_pos_list
+                    .iter()
+                    .zip(&saved.nodes)
+                    .map(|(node_pos, node)| {
+                        let saved_local = (node.pos - saved.center).rotated(-saved.rotation);
+                        circle.to_local(node_pos.pos).distance(saved_local) > MOVED_BY
+                    })
+                    .collect::<Vec<_>>();
+                circle.center = saved.center;
+                circle.radius = saved.radius;
+                circle.rotation = saved.rotation;
+                circle.origin = saved.origin;
+                circle.pinned = saved.pinned;
+                circle
+                    .node_pos_list
+                    .node_pos_list
+                    .iter_mut()
+                    .zip(&saved.nodes)
+                    .zip(moved)
+                    .for_each(|((node_pos, node), moved)| {
+                        node_pos.moved = moved;
+                        node_pos.pos = node.pos;
+                        node_pos.angle = node.angle;
+                    });
+            });
+
+        let nodes = glyph.nodes().map(|(node, _, _)| node).collect::<Vec<_>>();
+        glyph.edge_styles = self
+            .edge_styles
+            .iter()
+            .filter_map(|edge| {
+                let key = (
+                    *nodes.get(edge.source)?,
+                    *nodes.get(edge.target)?,
+                    edge.order,
+                );
+                Some((key, edge.style))
+            })
+            .collect();
+        Ok(glyph)
+    }
+}
+
+fn default_palette() -> Vec<Color> {
+    DEFAULT_PALETTE.to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //A glyph with a pinned circle, a moved node and an edge of its own style
+    fn edited_project() -> Project {
+        let rules = ConnectionTable::default();
+        let mut glyph = Glyph::with_rules("hello world", &rules).unwrap();
+        glyph.circles.circle_list[1].set_placement(Point::new(150.0, 20.0), 15.0);
+        let node = glyph.phrase.phrase_words[0].nodes[1];
+        glyph.circles.set_node_pos(node, Point::new(-5.0, -70.0));
+        let edge = glyph.phrase.graph.edge_indices().next().unwrap();
+        glyph.set_edge_style(edge, Some(EdgeStyle::Chord));
+        let theme = Theme {
+            ring_width: 7.5,
+            ..Theme::light()
+        };
+        Project::new("hello world", &glyph, "default", &rules, &theme)
+    }
+
+    #[test]
+    fn projects_round_trip() {
+        let mut project = edited_project();
+        let before = Project::new(
+            "hello",
+            &Glyph::new("hello").unwrap(),
+            "default",
+            &ConnectionTable::default(),
+            &Theme::dark(),
+        );
+        project.history.record(before.clone());
+        project.color_scheme = ColorScheme::Word;
+
+        let reopened = Project::from_json(&project.to_json()).unwrap();
+        assert_eq!(reopened, project);
+        assert_eq!(reopened.saved_theme().unwrap().ring_width, 7.5);
+        assert_eq!(reopened.history.clone().undo(project.clone()), Some(before));
+
+        let glyph = reopened.glyph().unwrap();
+        let circles = &glyph.circles.circle_list;
+        assert!(circles[1].pinned && !circles[0].pinned);
+        assert_eq!(circles[1].center, Point::new(150.0, 20.0));
+        let moved = circles[0]
+            .node_pos_list
+            .node_pos_list
+            .iter()
+            .map(|node_pos| node_pos.moved)
+            .collect::<Vec<_>>();
+        assert_eq!(moved, [false, true, false, false, false]);
+        assert_eq!(
+            circles[0].node_pos_list.node_pos_list[1].pos,
+            Point::new(-5.0, -70.0)
+        );
+        assert_eq!(
+            glyph.edge_styles.values().collect::<Vec<_>>(),
+            [&EdgeStyle::Chord]
+        );
+        //Saving the reopened glyph gives the same project again
+        let saved = Project::new(
+            &reopened.phrase,
+            &glyph,
+            &reopened.rules_name,
+            &reopened.connection_table().unwrap(),
+            &reopened.saved_theme().unwrap(),
+        );
+        assert_eq!(saved.circles, project.circles);
+        assert_eq!(saved.edge_styles, project.edge_styles);
+        assert_eq!(saved.custom_theme, project.custom_theme);
+    }
+
+    #[test]
+    fn built_in_themes_are_saved_by_name() {
+        let glyph = Glyph::new("hello").unwrap();
+        let rules = ConnectionTable::default();
+        let project = Project::new("hello", &glyph, "default", &rules, &Theme::light());
+        assert_eq!(project.custom_theme, None);
+        assert_eq!(project.saved_theme(), Some(Theme::light()));
+    }
+
+    #[test]
+    fn newer_and_broken_files_are_rejected() {
+        let json = edited_project().to_json();
+        let newer = json.replacen(
+            &format!("\"version\": {PROJECT_VERSION}"),
+            &format!("\"version\": {}", PROJECT_VERSION + 1),
+            1,
+        );
+        assert_ne!(newer, json);
+        assert!(matches!(
+            Project::from_json(&newer),
+            Err(ProjectError::Format(message)) if message.contains("newer")
+        ));
+        assert!(matches!(
+            Project::from_json("{\"phrase\": \"hello\"}"),
+            Err(ProjectError::Format(_))
+        ));
+
+        let mut renamed = edited_project();
+        renamed.phrase = String::from("hello there");
+        assert_eq!(renamed.glyph().unwrap_err(), ProjectError::Mismatch);
+    }
+}
@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+
+use crate::{loop_curve, Color, EdgeKind, Glyph, GlyphStyle, LabelFont, Point};
+
+/// A word circle ready to be drawn.
+#[derive(Clone, Debug)]
+pub struct Ring {
+    pub center: Point,
+    pub radius: f32,
+    pub width: f32,
+    pub color: Color,
+    pub word: usize,
+}
+
+impl Ring {
+    /// Points around the ring, starting and ending at the same one, with no
+    /// chord between them further than `tolerance` from the ring.
+    pub fn flatten(&self, tolerance: f32) -> Vec<Point> {
+        flatten_circle(self.center, self.radius, tolerance)
+    }
+}
+
+/// Points around a circle like [`Ring::flatten`], for circles that aren't rings.
+pub(crate) fn flatten_circle(center: Point, radius: f32, tolerance: f32) -> Vec<Point> {
+    let steps = if tolerance <= 0.0 || tolerance >= radius {
+        MIN_RING_STEPS
+    } else {
+        let step = 2.0 * (1.0 - tolerance / radius).acos();
+        ((std::f32::consts::TAU / step).ceil() as usize).clamp(MIN_RING_STEPS, MAX_STEPS)
+    };
+    (0..=steps)
+        .map(|i| center.polar(360.0 * (i % steps) as f32 / steps as f32, radius))
+        .collect()
+}
+
+/// The curve an edge is drawn along.
+#[derive(Clone, Debug)]
+pub enum EdgePath {
+    Quadratic([Point; 3]),
+    Cubic([Point; 4]),
+}
+
+impl EdgePath {
+    /// Points along the curve with no segment between them further than
+    /// `tolerance` from it.
+    pub fn flatten(&self, tolerance: f32) -> Vec<Point> {
+        //How far the curve bends away from its control polygon bounds the error of n straight steps
+        let (bend, factor) = match self {
+            EdgePath::Quadratic([p0, p1, p2]) => ((*p0 - *p1 * 2.0 + *p2).length(), 0.125),
+            EdgePath::Cubic([p0, p1, p2, p3]) => (
+                (*p0 - *p1 * 2.0 + *p2)
+                    .length()
+                    .max((*p1 - *p2 * 2.0 + *p3).length()),
+                0.75,
+            ),
+        };
+        let steps = match tolerance > 0.0 {
+            true => ((bend * factor / tolerance).sqrt().ceil() as usize).clamp(1, MAX_STEPS),
+            false => MAX_STEPS,
+        };
+        (0..=steps)
+            .map(|i| self.point_at(i as f32 / steps as f32))
+            .collect()
+    }
+
+    /// The point `t` of the way along the curve.
+    pub fn point_at(&self, t: f32) -> Point {
+        let s = 1.0 - t;
+        match self {
+            EdgePath::Quadratic([p0, p1, p2]) => {
+                *p0 * (s * s) + *p1 * (2.0 * s * t) + *p2 * (t * t)
+            }
+            EdgePath::Cubic([p0, p1, p2, p3]) => {
+                *p0 * (s * s * s)
+                    + *p1 * (3.0 * s * s * t)
+                    + *p2 * (3.0 * s * t * t)
+                    + *p3 * (t * t * t)
+            }
+        }
+    }
+}
+
+/// An edge ready to be drawn.
+#[derive(Clone, Debug)]
+pub struct EdgeStroke {
+    pub path: EdgePath,
+    pub width: f32,
+    pub color: Color,
+    /// The word of the edge's source node.
+    pub word: usize,
+    pub kind: EdgeKind,
+}
+
+/// A letter node ready to be drawn.
+#[derive(Clone, Debug)]
+pub struct Dot {
+    pub center: Point,
+    pub radius: f32,
+    pub color: Color,
+    pub letter: char,
+    pub word: usize,
+}
+
+/// A letter label, centered horizontally on `pos` with its baseline at `pos.y`.
+#[derive(Clone, Debug)]
+pub struct Label {
+    pub pos: Point,
+    pub size: f32,
+    pub color: Color,
+    pub text: char,
+}
+
+/// The area of the canvas a scene covers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Bounds {
+    pub fn width(&self) -> f32 {
+        self.max.x - self.min.x
+    }
+
+    pub fn height(&self) -> f32 {
+        self.max.y - self.min.y
+    }
+
+    pub fn center(&self) -> Point {
+        self.min.lerp(self.max, 0.5)
+    }
+
+    fn include(&mut self, point: Point, pad: f32) {
+        self.min.x = self.min.x.min(point.x - pad);
+        self.min.y = self.min.y.min(point.y - pad);
+        self.max.x = self.max.x.max(point.x + pad);
+        self.max.y = self.max.y.max(point.y + pad);
+    }
+}
+
+/// Everything needed to draw a glyph, resolved against a [`GlyphStyle`].
+///
+/// Exporters walk a scene instead of the glyph so every output format draws
+/// the same shapes the app does.
+#[derive(Clone, Debug)]
+pub struct Scene {
+    pub bounds: Bounds,
+    pub background: Color,
+    pub rings: Vec<Ring>,
+    pub edges: Vec<EdgeStroke>,
+    pub nodes: Vec<Dot>,
+    pub labels: Vec<Label>,
+    pub label_font: LabelFont,
+}
+
+//Empty space kept around the glyph
+const MARGIN: f32 = 10.0;
+//Bounds on how many straight steps a flattened ring or curve is made of
+const MIN_RING_STEPS: usize = 16;
+const MAX_STEPS: usize = 1024;
+
+impl Scene {
+    pub fn new(glyph: &Glyph, style: &GlyphStyle) -> Self {
+        let rings = glyph
+            .circles
+            .circle_list
+            .iter()
+            .enumerate()
+            .map(|(word, circle)| Ring {
+                center: circle.center,
+                radius: circle.radius,
+                width: style.ring_width,
+                color: style.ring_color_of(word),
+                word,
+            })
+            .collect::<Vec<_>>();
+
+        let nodes = glyph
+            .circles
+            .circle_list
+            .iter()
+            .enumerate()
+            .flat_map(|(word, circle)| {
+                circle
+                    .word
+                    .word
+                    .chars()
+                    .zip(circle.node_pos_list.node_pos_list.iter())
+                    .map(move |(letter, node_pos)| (node_pos.node, letter, node_pos.pos, word))
+            })
+            .collect::<Vec<_>>();
+        let positions = nodes
+            .iter()
+            .map(|(node, letter, pos, word)| (*node, (*pos, *word, *letter)))
+            .collect::<HashMap<_, _>>();
+
+        let edges = glyph
+            .edges()
+            .filter_map(|(source, target, order, kind)| {
+                let (start, word, letter) = positions.get(&source)?;
+                let (end, _, _) = positions.get(&target)?;
+                let path = if source == target {
+                    EdgePath::Cubic(loop_curve(
+                        *start,
+                        style.node_radius,
+                        style.loop_size,
+                        order,
+                    ))
+                } else {
+                    let edge_style = glyph
+                        .edge_styles
+                        .get(&(source, target, order))
+                        .unwrap_or(&style.edge_style);
+                    //Edges between words don't sit on a single ring
+                    let ring = (kind == EdgeKind::Within)
+                        .then(|| rings.get(*word).map(|ring| (ring.center, ring.radius)))
+                        .flatten();
+                    EdgePath::Quadratic(edge_style.curve(
+                        [*start, *end],
+                        [style.node_radius; 2],
+                        style.curve_size,
+                        order,
+                        ring,
+                    ))
+                };
+                Some(EdgeStroke {
+                    path,
+                    width: style.edge_width,
+                    color: style.edge_color_of(*word, *letter, kind),
+                    word: *word,
+                    kind,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let labels = if style.labels {
+            nodes
+                .iter()
+                .map(|(_, letter, pos, word)| Label {
+                    pos: Point::new(pos.x, pos.y - style.node_radius),
+                    size: style.label_size,
+                    color: style.label_color_of(*word, *letter),
+                    text: *letter,
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+
+        let nodes = nodes
+            .into_iter()
+            .map(|(_, letter, center, word)| Dot {
+                center,
+                radius: style.node_radius,
+                color: style.node_color_of(word, letter),
+                letter,
+                word,
+            })
+            .collect::<Vec<_>>();
+
+        let mut scene = Scene {
+            bounds: Bounds {
+                min: Point::new(f32::MAX, f32::MAX),
+                max: Point::new(f32::MIN, f32::MIN),
+            },
+            background: style.background,
+            rings,
+            edges,
+            nodes,
+            labels,
+            label_font: style.label_font,
+        };
+        scene.bounds = scene.measure();
+        scene
+    }
+
+    fn measure(&self) -> Bounds {
+        let mut bounds = self.bounds;
+        self.rings
+            .iter()
+            .for_each(|ring| bounds.include(ring.center, ring.radius + ring.width / 2.0));
+        self.edges.iter().for_each(|edge| {
+            //A Bezier curve never leaves the hull of its control points
+            let points: &[Point] = match &edge.path {
+                EdgePath::Quadratic(points) => points,
+                EdgePath::Cubic(points) => points,
+            };
+            points
+                .iter()
+                .for_each(|point| bounds.include(*point, edge.width / 2.0));
+        });
+        self.nodes
+            .iter()
+            .for_each(|node| bounds.include(node.center, node.radius));
+        self.labels
+            .iter()
+            .for_each(|label| bounds.include(label.pos, label.size));
+
+        if bounds.min.x > bounds.max.x {
+            bounds = Bounds {
+                min: Point::default(),
+                max: Point::default(),
+            };
+        }
+        bounds.include(bounds.min, MARGIN);
+        bounds.include(bounds.max, MARGIN);
+        bounds
+    }
+}
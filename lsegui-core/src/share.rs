@@ -0,0 +1,300 @@
+use crate::number::num;
+use crate::{ConnectionTable, Glyph, GlyphError, Point};
+
+//Keep a tenth of a unit, which is far finer than anyone can drag a node
+const DECIMALS: usize = 1;
+
+/// A glyph as carried in the fragment of a share link: the phrase, the
+/// connection table when it isn't the bundled one, and every circle and
+/// node that was moved away from where the layout put it.
+///
+/// Fragments look like `p=Default%20Phrase&c=1:80:-20:45&m=3:12.5:-40&r=A.B-A.C-AB&w=1`,
+/// where `w` is the reach of the table.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SharedGlyph {
+    pub phrase: String,
+    pub rules: Option<ConnectionTable>,
+    /// Pinned circles by the position of their word, with their center and rotation.
+    pub circles: Vec<(usize, Point, f32)>,
+    /// Moved nodes by the position of their letter in the whole phrase.
+    pub moved: Vec<(usize, Point)>,
+}
+
+impl SharedGlyph {
+    /// Captures `glyph` as built from `phrase` with `rules`.
+    pub fn new(phrase: &str, glyph: &Glyph, rules: &ConnectionTable) -> Self {
+        //Node indices change as the phrase is edited, letter positions don't
+        let moved = glyph
+            .circles
+            .circle_list
+            .iter()
+            .flat_map(|circle| circle.node_pos_list.node_pos_list.iter())
+            .enumerate()
+            .filter(|(_, node_pos)| node_pos.moved)
+            .map(|(i, node_pos)| (i, node_pos.pos))
+            .collect();
+
+        let circles = glyph
+            .circles
+            .circle_list
+            .iter()
+            .enumerate()
+            .filter(|(_, circle)| circle.pinned)
+            .map(|(i, circle)| (i, circle.center, circle.rotation))
+            .collect();
+
+        SharedGlyph {
+            phrase: phrase.to_string(),
+            rules: (*rules != ConnectionTable::default()).then(|| rules.clone()),
+            circles,
+            moved,
+        }
+    }
+
+    pub fn to_fragment(&self) -> String {
+        let mut fragment = format!("p={}", percent_encode(&self.phrase));
+        if !self.circles.is_empty() {
+            let circles = self
+                .circles
+                .iter()
+                .map(|(i, center, rotation)| {
+                    format!(
+                        "{}:{}:{}:{}",
+                        i,
+                        num(center.x, DECIMALS),
+                        num(center.y, DECIMALS),
+                        num(*rotation, DECIMALS)
+                    )
+                })
+                .collect::<Vec<_>>();
+            fragment.push_str(&format!("&c={}", circles.join(",")));
+        }
+        if !self.moved.is_empty() {
+            let moved = self
+                .moved
+                .iter()
+                .map(|(i, pos)| format!("{}:{}:{}", i, num(pos.x, DECIMALS), num(pos.y, DECIMALS)))
+                .collect::<Vec<_>>();
+            fragment.push_str(&format!("&m={}", moved.join(",")));
+        }
+        if let Some(rules) = &self.rules {
+            //A table that only differs in its reach doesn't need its letters spelled out
+            let bundled = ConnectionTable::default();
+            if !rules.letters().eq(bundled.letters()) {
+                let letters = rules
+                    .letters()
+                    .map(|(letter, targets)| match targets {
+                        "" => letter.to_string(),
+                        _ => format!("{letter}-{targets}"),
+                    })
+                    .collect::<Vec<_>>();
+                fragment.push_str(&format!("&r={}", letters.join(".")));
+            }
+            if rules.reach() > 0 {
+                fragment.push_str(&format!("&w={}", rules.reach()));
+            }
+        }
+        fragment
+    }
+
+    /// Reads a fragment written by [`SharedGlyph::to_fragment`], with or
+    /// without its leading `#`. Returns `None` when there is no phrase in it.
+    ///
+    /// Damaged moves are skipped and a damaged table falls back to the
+    /// bundled one, so a link that was cut short still shows something.
+    pub fn from_fragment(fragment: &str) -> Option<Self> {
+        let mut shared = SharedGlyph::default();
+        let mut phrase = None;
+        let mut reach = None;
+        fragment
+            .trim_start_matches('#')
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .for_each(|(key, value)| match key {
+                "p" => phrase = percent_decode(value),
+                "c" => shared.circles = value.split(',').filter_map(parse_circle).collect(),
+                "m" => shared.moved = value.split(',').filter_map(parse_move).collect(),
+                "r" => shared.rules = parse_rules(value),
+                "w" => reach = value.parse::<usize>().ok(),
+                _ => (),
+            });
+        if let Some(reach) = reach {
+            shared
+                .rules
+                .get_or_insert_with(ConnectionTable::default)
+                .set_reach(reach);
+        }
+        shared.phrase = phrase.filter(|phrase| !phrase.trim().is_empty())?;
+        Some(shared)
+    }
+
+    /// Rebuilds the glyph and moves the circles and nodes back to where they
+    /// were shared from.
+    pub fn glyph(&self) -> Result<Glyph, GlyphError> {
+        let rules = self.rules.clone().unwrap_or_default();
+        let mut glyph = Glyph::with_rules(&self.phrase, &rules)?;
+        self.circles.iter().for_each(|(i, center, rotation)| {
+            if let Some(circle) = glyph.circles.circle_list.get_mut(*i) {
+                circle.set_placement(*center, *rotation);
+            }
+        });
+        let nodes = glyph.nodes().map(|(node, _, _)| node).collect::<Vec<_>>();
+        self.moved
+            .iter()
+            .filter_map(|(i, pos)| Some((*nodes.get(*i)?, *pos)))
+            .for_each(|(node, pos)| glyph.circles.set_node_pos(node, pos));
+        Ok(glyph)
+    }
+}
+
+fn parse_move(entry: &str) -> Option<(usize, Point)> {
+    let mut parts = entry.split(':');
+    let i = parts.next()?.parse::<usize>().ok()?;
+    let x = parts.next()?.parse::<f32>().ok()?;
+    let y = parts.next()?.parse::<f32>().ok()?;
+    Some((i, Point::new(x, y)))
+}
+
+fn parse_circle(entry: &str) -> Option<(usize, Point, f32)> {
+    let mut parts = entry.split(':');
+    let i = parts.next()?.parse::<usize>().ok()?;
+    let x = parts.next()?.parse::<f32>().ok()?;
+    let y = parts.next()?.parse::<f32>().ok()?;
+    let rotation = parts.next()?.parse::<f32>().ok()?;
+    Some((i, Point::new(x, y), rotation))
+}
+
+fn parse_rules(value: &str) -> Option<ConnectionTable> {
+    let mut toml = String::from("[connections]\n");
+    value.split('.').for_each(|entry| {
+        let (letter, targets) = entry.split_once('-').unwrap_or((entry, ""));
+        toml.push_str(&format!("{letter} = \"{targets}\"\n"));
+    });
+    ConnectionTable::parse(&toml).ok()
+}
+
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+fn percent_decode(text: &str) -> Option<String> {
+    let mut bytes = vec![];
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        match byte {
+            b'%' => {
+                //from_str_radix would also take a sign, as in %+f
+                let hex = tail
+                    .get(..2)
+                    .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))?;
+                bytes.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+                rest = &tail[2..];
+            }
+            b'+' => {
+                bytes.push(b' ');
+                rest = tail;
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DEFAULT_RULES;
+
+    #[test]
+    fn moved_nodes_pinned_circles_and_rules_round_trip() {
+        let rules = ConnectionTable::parse(&DEFAULT_RULES.replace("B = \"A\"", "B = \"\""));
+        let mut rules = rules.unwrap();
+        rules.set_reach(1);
+        let mut glyph = Glyph::with_rules("hello world", &rules).unwrap();
+        glyph.circles.circle_list[1].set_placement(Point::new(120.5, -40.0), 32.5);
+        let node = glyph.phrase.phrase_words[0].nodes[2];
+        glyph.circles.set_node_pos(node, Point::new(-12.5, 30.0));
+
+        let shared = SharedGlyph::new("hello world", &glyph, &rules);
+        assert_eq!(shared.circles, [(1, Point::new(120.5, -40.0), 32.5)]);
+        assert_eq!(shared.moved, [(2, Point::new(-12.5, 30.0))]);
+        let fragment = shared.to_fragment();
+        [
+            "p=hello%20world",
+            "&c=1:120.5:-40:32.5",
+            "&m=2:-12.5:30",
+            "&r=",
+            "&w=1",
+        ]
+        .iter()
+        .for_each(|part| assert!(fragment.contains(part), "{fragment}"));
+        assert_eq!(
+            SharedGlyph::from_fragment(&format!("#{fragment}")),
+            Some(shared.clone())
+        );
+
+        let rebuilt = shared.glyph().unwrap();
+        assert_eq!(
+            rebuilt.circles.circle_list[1].center,
+            Point::new(120.5, -40.0)
+        );
+        let moved = rebuilt.circles.circle_list[0].get_node_pos(node);
+        assert_eq!(moved, Some(Point::new(-12.5, 30.0)));
+    }
+
+    #[test]
+    fn a_reach_alone_leaves_the_letters_out() {
+        let mut rules = ConnectionTable::default();
+        rules.set_reach(2);
+        let glyph = Glyph::with_rules("hello", &rules).unwrap();
+        let fragment = SharedGlyph::new("hello", &glyph, &rules).to_fragment();
+        assert_eq!(fragment, "p=hello&w=2");
+        let shared = SharedGlyph::from_fragment(&fragment).unwrap();
+        assert_eq!(shared.rules, Some(rules));
+    }
+
+    #[test]
+    fn phrases_outside_ascii_round_trip() {
+        let shared = SharedGlyph {
+            phrase: String::from("Grüße, café & 東京 + 100%"),
+            ..SharedGlyph::default()
+        };
+        let fragment = shared.to_fragment();
+        assert!(fragment.is_ascii());
+        assert_eq!(SharedGlyph::from_fragment(&fragment), Some(shared));
+        //Spaces written as + by forms read back as well
+        assert_eq!(
+            SharedGlyph::from_fragment("p=hello+world").unwrap().phrase,
+            "hello world"
+        );
+    }
+
+    #[test]
+    fn damaged_escapes_are_rejected() {
+        [
+            "p=%",
+            "p=ab%4",
+            "p=%+f",
+            "p=%-1",
+            "p=%zz",
+            "p=%C3",
+            "p=%20%20",
+            "c=0:1:2:3",
+        ]
+        .iter()
+        .for_each(|fragment| assert_eq!(SharedGlyph::from_fragment(fragment), None, "{fragment}"));
+        //A damaged move is skipped rather than losing the phrase
+        let shared = SharedGlyph::from_fragment("p=a&m=0:1,x:2:3").unwrap();
+        assert!(shared.moved.is_empty());
+    }
+}
@@ -0,0 +1,649 @@
+use std::collections::VecDeque;
+use std::fmt::Write;
+
+use crate::dxf::{dot_holes, edge_layer, layers, outline, write_dxf, Cut, Sheet, DECIMALS};
+use crate::number::num;
+use crate::{DxfOptions, Point, Scene};
+
+/// Writes a scene as a DXF stencil, laid out like [`to_dxf`](crate::to_dxf)
+/// but with the rings cut as slots and bridges left across them.
+pub fn to_stencil_dxf(scene: &Scene, options: &DxfOptions) -> Result<String, String> {
+    let cuts = stencil_cuts(scene, options)?;
+    Ok(write_dxf(&layers(scene), &cuts))
+}
+
+/// Writes a scene as an SVG stencil, with every cut a black shape on a white
+/// sheet measured in millimetres.
+pub fn to_stencil_svg(scene: &Scene, options: &DxfOptions) -> Result<String, String> {
+    let sheet = Sheet::new(scene, options)?;
+    let cuts = stencil_cuts(scene, options)?;
+    //SVG counts y downwards from the top of the sheet
+    let flip = |point: &Point| {
+        (
+            num(point.x, DECIMALS),
+            num(sheet.height - point.y, DECIMALS),
+        )
+    };
+    let mut svg = String::new();
+
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {0} {1}" width="{0}mm" height="{1}mm">"#,
+        num(sheet.width, DECIMALS),
+        num(sheet.height, DECIMALS),
+    )
+    .unwrap();
+    writeln!(
+        svg,
+        r##"  <rect width="{}" height="{}" fill="#ffffff"/>"##,
+        num(sheet.width, DECIMALS),
+        num(sheet.height, DECIMALS),
+    )
+    .unwrap();
+    writeln!(svg, r##"  <g fill="#000000">"##).unwrap();
+    cuts.iter().for_each(|(_, cut)| match cut {
+        Cut::Circle(center, radius) => {
+            let (x, y) = flip(center);
+            writeln!(
+                svg,
+                r#"    <circle cx="{x}" cy="{y}" r="{}"/>"#,
+                num(*radius, DECIMALS)
+            )
+            .unwrap();
+        }
+        Cut::Outline(points) => {
+            let d = points
+                .iter()
+                .map(|point| {
+                    let (x, y) = flip(point);
+                    format!("{x} {y}")
+                })
+                .collect::<Vec<_>>()
+                .join(" L ");
+            writeln!(svg, r#"    <path d="M {d} Z"/>"#).unwrap();
+        }
+    });
+    writeln!(svg, "  </g>").unwrap();
+    writeln!(svg, "</svg>").unwrap();
+    Ok(svg)
+}
+
+//How many times islands are looked for and bridged before the stencil is given up on
+const MAX_ROUNDS: usize = 8;
+//How many cells of the grid islands are found on fit across a bridge or a slot,
+//and how many cells the grid may have at most
+const CELLS_ACROSS: f32 = 4.0;
+const MAX_CELLS: f32 = 4_000_000.0;
+
+//Everything a stencil cuts out, each on the layer of its word.
+//
+//The cuts are drawn on a grid to find the islands of material they enclose,
+//which would fall out of the sheet. Every island gets bridges along the
+//shortest ways across the cuts to material that is held, with a gap in every
+//slot such a way crosses. That is repeated until nothing is left enclosed,
+//or the stencil is given up on if that takes too many rounds.
+fn stencil_cuts(scene: &Scene, options: &DxfOptions) -> Result<Vec<(usize, Cut)>, String> {
+    bridged_cuts(scene, options, MAX_ROUNDS).map(|(cuts, _)| cuts)
+}
+
+//The cuts of a stencil bridged in up to `rounds` rounds, with how many bridges it took
+fn bridged_cuts(
+    scene: &Scene,
+    options: &DxfOptions,
+    rounds: usize,
+) -> Result<(Vec<(usize, Cut)>, usize), String> {
+    let sheet = Sheet::new(scene, options)?;
+    if options.bridges == 0 || options.bridge_width <= 0.0 {
+        return Err(String::from(
+            "a stencil needs bridges wider than 0 mm or its islands fall out",
+        ));
+    }
+
+    let strokes =
+        scene
+            .rings
+            .iter()
+            .map(|ring| Stroke::new(sheet.ring_line(ring), true, ring.word))
+            .chain(scene.edges.iter().map(|edge| {
+                Stroke::new(sheet.edge_line(&edge.path), false, edge_layer(scene, edge))
+            }))
+            .filter(|stroke| stroke.line.len() > 1)
+            .collect::<Vec<_>>();
+    let holes = dot_holes(scene, &sheet, options).collect::<Vec<_>>();
+
+    let cell = cell_size(&sheet, options);
+    //Material narrower than half a bridge neither holds anything nor stays in one piece
+    let margin = options.bridge_width / 4.0;
+    let half_gap = (options.bridge_width + options.kerf) / 2.0;
+    let reach = options.edge_width / 2.0 + cell;
+
+    let mut gaps = vec![vec![]; strokes.len()];
+    for round in 0..=rounds {
+        let grid = Grid::new(
+            &sheet,
+            cell,
+            &slots(&strokes, &gaps, sheet.half_width),
+            &holes,
+        );
+        let paths = grid.bridge_paths(margin, options.bridges, 4.0 * options.bridge_width);
+        if paths.is_empty() {
+            break;
+        }
+        //Pieces that are still enclosed would fall out of the cut sheet
+        if round == rounds {
+            return Err(format!(
+                "{} islands are still enclosed after {rounds} rounds of bridges, try wider bridges or more of them",
+                grid.islands(margin)
+            ));
+        }
+
+        //Every slot a bridge crosses gets a gap where it does
+        paths.iter().for_each(|path| {
+            let centers = path
+                .iter()
+                .map(|cell| grid.center(*cell))
+                .collect::<Vec<_>>();
+            strokes
+                .iter()
+                .zip(gaps.iter_mut())
+                .for_each(|(stroke, gaps)| {
+                    let crossed = centers
+                        .iter()
+                        .filter(|center| stroke.near(**center, reach))
+                        .map(|center| stroke.closest(*center))
+                        .filter(|(_, distance)| *distance <= reach)
+                        .map(|(along, _)| along)
+                        .collect::<Vec<_>>();
+                    let Some(first) = crossed.first().copied() else {
+                        return;
+                    };
+                    //Rings are crossed near wherever they were first, not a lap away
+                    let length = stroke.length();
+                    let crossed = crossed.iter().map(|along| match stroke.closed {
+                        true => {
+                            first + (along - first + length / 2.0).rem_euclid(length) - length / 2.0
+                        }
+                        false => *along,
+                    });
+                    let (from, to) = crossed.fold((first, first), |(from, to), along| {
+                        (from.min(along), to.max(along))
+                    });
+                    gaps.push((from - half_gap, to + half_gap));
+                });
+        });
+    }
+
+    let mut cuts = slots(&strokes, &gaps, sheet.half_width);
+    cuts.extend(holes);
+    Ok((cuts, gaps.iter().map(Vec::len).sum()))
+}
+
+//How wide the cells of the grid islands are found on are
+fn cell_size(sheet: &Sheet, options: &DxfOptions) -> f32 {
+    (options.bridge_width.min(options.edge_width) / CELLS_ACROSS)
+        .max((sheet.width * sheet.height / MAX_CELLS).sqrt())
+}
+
+//The outlines of what is left of every stroke once the gaps are taken out
+fn slots(strokes: &[Stroke], gaps: &[Vec<(f32, f32)>], half_width: f32) -> Vec<(usize, Cut)> {
+    strokes
+        .iter()
+        .zip(gaps)
+        .flat_map(|(stroke, gaps)| {
+            stroke
+                .kept(gaps)
+                .into_iter()
+                .filter_map(|(from, to)| outline(&stroke.stretch(from, to), half_width))
+                .map(|outline| (stroke.layer, Cut::Outline(outline)))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+//What covers a cell of the grid
+#[derive(Clone, Copy, PartialEq)]
+enum Cover {
+    Material,
+    Slot,
+    Dot,
+}
+
+//The sheet split into square cells, counted in rows from the bottom left
+struct Grid {
+    cell: f32,
+    width: usize,
+    height: usize,
+    cover: Vec<Cover>,
+}
+
+impl Grid {
+    fn new(sheet: &Sheet, cell: f32, slots: &[(usize, Cut)], holes: &[(usize, Cut)]) -> Self {
+        let width = (sheet.width / cell).ceil() as usize + 1;
+        let height = (sheet.height / cell).ceil() as usize + 1;
+        let mut grid = Grid {
+            cell,
+            width,
+            height,
+            cover: vec![Cover::Material; width * height],
+        };
+        slots.iter().chain(holes).for_each(|(_, cut)| match cut {
+            Cut::Outline(points) => grid.fill(points, Cover::Slot),
+            Cut::Circle(center, radius) => {
+                let points = (0..32)
+                    .map(|i| center.polar(i as f32 * 360.0 / 32.0, *radius))
+                    .collect::<Vec<_>>();
+                grid.fill(&points, Cover::Dot);
+            }
+        });
+        grid
+    }
+
+    //Covers every cell whose center is inside the polygon
+    fn fill(&mut self, polygon: &[Point], cover: Cover) {
+        let (low, high) = polygon
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(low, high), point| {
+                (low.min(point.y), high.max(point.y))
+            });
+        let first = (low / self.cell - 0.5).ceil().max(0.0) as usize;
+        let last = ((high / self.cell - 0.5).floor().max(0.0) as usize).min(self.height - 1);
+        (first..=last).for_each(|row| {
+            let y = (row as f32 + 0.5) * self.cell;
+            let mut crossings = polygon
+                .iter()
+                .zip(polygon.iter().cycle().skip(1))
+                .filter(|(a, b)| (a.y <= y) != (b.y <= y))
+                .map(|(a, b)| a.x + (y - a.y) * (b.x - a.x) / (b.y - a.y))
+                .collect::<Vec<_>>();
+            crossings.sort_by(f32::total_cmp);
+            crossings.chunks_exact(2).for_each(|span| {
+                let start = (span[0] / self.cell - 0.5).ceil().max(0.0) as usize;
+                let end = (span[1] / self.cell - 0.5).floor();
+                if end >= 0.0 {
+                    (start..=(end as usize).min(self.width - 1))
+                        .for_each(|column| self.cover[row * self.width + column] = cover);
+                }
+            });
+        });
+    }
+
+    fn center(&self, index: usize) -> Point {
+        Point::new(
+            ((index % self.width) as f32 + 0.5) * self.cell,
+            ((index / self.width) as f32 + 0.5) * self.cell,
+        )
+    }
+
+    fn neighbours(&self, index: usize) -> impl Iterator<Item = usize> {
+        let (column, row) = (index % self.width, index / self.width);
+        [
+            (column > 0).then(|| index - 1),
+            (column + 1 < self.width).then(|| index + 1),
+            (row > 0).then(|| index - self.width),
+            (row + 1 < self.height).then(|| index + self.width),
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    //Which cells hold material at least `margin` away from every cut
+    fn solid(&self, margin: f32) -> Vec<bool> {
+        //Chamfer distances in cells to the closest cut, swept forwards and then backwards
+        let mut distance = self
+            .cover
+            .iter()
+            .map(|cover| match cover {
+                Cover::Material => f32::INFINITY,
+                _ => 0.0,
+            })
+            .collect::<Vec<_>>();
+        let steps = [
+            (-1, 0, 1.0),
+            (-1, -1, std::f32::consts::SQRT_2),
+            (0, -1, 1.0),
+            (1, -1, std::f32::consts::SQRT_2),
+        ];
+        let (width, height) = (self.width as isize, self.height as isize);
+        let mut sweep = |index: usize, sign: isize| {
+            let (column, row) = ((index % self.width) as isize, (index / self.width) as isize);
+            steps.iter().for_each(|(x, y, step)| {
+                let (column, row) = (column + x * sign, row + y * sign);
+                if (0..width).contains(&column) && (0..height).contains(&row) {
+                    let through = distance[(row * width + column) as usize] + step;
+                    distance[index] = distance[index].min(through);
+                }
+            });
+        };
+        (0..self.cover.len()).for_each(|index| sweep(index, 1));
+        (0..self.cover.len())
+            .rev()
+            .for_each(|index| sweep(index, -1));
+        //Distances run between cell centers, half a cell past the edge of a cut
+        distance
+            .into_iter()
+            .map(|distance| (distance - 0.5) * self.cell >= margin)
+            .collect()
+    }
+
+    //Which piece of solid material every cell belongs to, and whether each
+    //piece reaches the edge of the sheet
+    fn pieces(&self, solid: &[bool]) -> (Vec<usize>, Vec<bool>) {
+        let mut piece = vec![usize::MAX; solid.len()];
+        let mut on_edge = vec![];
+        (0..solid.len()).for_each(|start| {
+            if !solid[start] || piece[start] != usize::MAX {
+                return;
+            }
+            let label = on_edge.len();
+            let mut edge = false;
+            let mut stack = vec![start];
+            piece[start] = label;
+            while let Some(index) = stack.pop() {
+                let (column, row) = (index % self.width, index / self.width);
+                edge |=
+                    column == 0 || row == 0 || column + 1 == self.width || row + 1 == self.height;
+                self.neighbours(index).for_each(|next| {
+                    if solid[next] && piece[next] == usize::MAX {
+                        piece[next] = label;
+                        stack.push(next);
+                    }
+                });
+            }
+            on_edge.push(edge);
+        });
+        (piece, on_edge)
+    }
+
+    //How many pieces of material aren't held by the sheet around them
+    fn islands(&self, margin: f32) -> usize {
+        let (_, held) = self.pieces(&self.solid(margin));
+        held.into_iter().filter(|held| !held).count()
+    }
+
+    //The cells bridges go across, as the shortest ways from every island
+    //through the cuts and around the dots to material that is held.
+    //Up to `bridges` of them are spread around each island at least `spacing` apart.
+    fn bridge_paths(&self, margin: f32, bridges: usize, spacing: f32) -> Vec<Vec<usize>> {
+        let solid = self.solid(margin);
+        let (piece, held) = self.pieces(&solid);
+        let open = |index: usize| !solid[index] && self.cover[index] != Cover::Dot;
+
+        //Which way every open cell goes towards held material, and in how many steps
+        let mut towards = vec![None; solid.len()];
+        let mut queue = (0..solid.len())
+            .filter(|index| open(*index))
+            .filter(|index| {
+                self.neighbours(*index)
+                    .any(|next| solid[next] && held[piece[next]])
+            })
+            .collect::<VecDeque<_>>();
+        queue
+            .iter()
+            .for_each(|index| towards[*index] = Some((*index, 1)));
+        while let Some(index) = queue.pop_front() {
+            let steps = towards[index].map_or(0, |(_, steps)| steps);
+            self.neighbours(index).for_each(|next| {
+                if open(next) && towards[next].is_none() {
+                    towards[next] = Some((index, steps + 1));
+                    queue.push_back(next);
+                }
+            });
+        }
+
+        //Where the ways from every island start, shortest first
+        let mut starts = vec![vec![]; held.len()];
+        (0..solid.len()).for_each(|index| {
+            let Some((_, steps)) = towards[index] else {
+                return;
+            };
+            self.neighbours(index)
+                .filter(|next| solid[*next] && !held[piece[*next]])
+                .for_each(|next| starts[piece[next]].push((steps, index)));
+        });
+
+        let path = |start: usize| {
+            let mut path = vec![start];
+            while let Some((next, steps)) = towards[*path.last().unwrap()] {
+                if steps == 1 {
+                    break;
+                }
+                path.push(next);
+            }
+            path
+        };
+        starts
+            .into_iter()
+            .filter(|starts| !starts.is_empty())
+            .flat_map(|mut starts| {
+                starts.sort_unstable();
+                starts.dedup();
+                //Ways much longer than the shortest one aren't worth a bridge
+                let longest = starts[0].0 * 3 / 2 + 2;
+                let starts = starts
+                    .into_iter()
+                    .take_while(|(steps, _)| *steps <= longest)
+                    .map(|(_, start)| start)
+                    .collect::<Vec<_>>();
+                //The shortest way first, then whichever starts furthest from those picked
+                let mut picked = vec![starts[0]];
+                while picked.len() < bridges {
+                    let furthest = starts
+                        .iter()
+                        .map(|start| {
+                            let apart = picked
+                                .iter()
+                                .map(|picked| self.center(*picked).distance(self.center(*start)))
+                                .fold(f32::MAX, f32::min);
+                            (*start, apart)
+                        })
+                        .filter(|(_, apart)| *apart >= spacing)
+                        .max_by(|a, b| a.1.total_cmp(&b.1));
+                    match furthest {
+                        Some((start, _)) => picked.push(start),
+                        None => break,
+                    }
+                }
+                picked.into_iter().map(path).collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+//A line cut along the sheet, with how far along it every one of its points is
+struct Stroke {
+    line: Vec<Point>,
+    along: Vec<f32>,
+    closed: bool,
+    layer: usize,
+    //Corners of the box around the line, to skip strokes nowhere near a point
+    min: Point,
+    max: Point,
+}
+
+impl Stroke {
+    fn new(line: Vec<Point>, closed: bool, layer: usize) -> Self {
+        let along = line
+            .iter()
+            .scan((0.0, line[0]), |(length, last), point| {
+                *length += last.distance(*point);
+                *last = *point;
+                Some(*length)
+            })
+            .collect();
+        let (min, max) = line.iter().fold((line[0], line[0]), |(min, max), point| {
+            (
+                Point::new(min.x.min(point.x), min.y.min(point.y)),
+                Point::new(max.x.max(point.x), max.y.max(point.y)),
+            )
+        });
+        Stroke {
+            line,
+            along,
+            closed,
+            layer,
+            min,
+            max,
+        }
+    }
+
+    //Whether any of the stroke could be within `reach` of a point
+    fn near(&self, point: Point, reach: f32) -> bool {
+        self.min.x - reach <= point.x
+            && self.min.y - reach <= point.y
+            && self.max.x + reach >= point.x
+            && self.max.y + reach >= point.y
+    }
+
+    fn length(&self) -> f32 {
+        *self.along.last().unwrap()
+    }
+
+    //Closed strokes go round and round, open ones stop at their ends
+    fn point_at(&self, along: f32) -> Point {
+        let along = match self.closed {
+            true => along.rem_euclid(self.length()),
+            false => along.clamp(0.0, self.length()),
+        };
+        let i = self
+            .along
+            .partition_point(|at| *at <= along)
+            .clamp(1, self.line.len() - 1);
+        let step = self.along[i] - self.along[i - 1];
+        let t = match step > 0.0 {
+            true => (along - self.along[i - 1]) / step,
+            false => 0.0,
+        };
+        self.line[i - 1].lerp(self.line[i], t)
+    }
+
+    //The part of the line between two distances along it, which for closed
+    //strokes may carry on past the end into the next lap
+    fn stretch(&self, from: f32, to: f32) -> Vec<Point> {
+        let laps = match self.closed {
+            true => vec![0.0, self.length()],
+            false => vec![0.0],
+        };
+        let inner = laps.into_iter().flat_map(|lap| {
+            self.along
+                .iter()
+                .zip(self.line.iter())
+                .filter(move |(along, _)| **along + lap > from && **along + lap < to)
+                .map(|(_, point)| *point)
+        });
+        std::iter::once(self.point_at(from))
+            .chain(inner)
+            .chain(std::iter::once(self.point_at(to)))
+            .collect()
+    }
+
+    //How far along the stroke its closest point to `point` is, and how far away
+    fn closest(&self, point: Point) -> (f32, f32) {
+        self.line
+            .windows(2)
+            .zip(self.along.iter())
+            .map(|(segment, along)| {
+                let t = closest_on(segment, point);
+                let length = segment[0].distance(segment[1]);
+                (
+                    along + t * length,
+                    segment[0].lerp(segment[1], t).distance(point),
+                )
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap_or((0.0, f32::MAX))
+    }
+
+    //The stretches left to cut once the gaps for bridges are taken out
+    fn kept(&self, gaps: &[(f32, f32)]) -> Vec<(f32, f32)> {
+        let length = self.length();
+        let mut gaps = gaps.to_vec();
+        if self.closed {
+            gaps.iter_mut().for_each(|(from, to)| {
+                let start = from.rem_euclid(length);
+                *to += start - *from;
+                *from = start;
+            });
+        }
+        gaps.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        //Closed strokes are cut from the end of their first gap round to its start
+        let mut kept = vec![];
+        let (mut cursor, end) = match (self.closed, gaps.first()) {
+            (true, Some(&(from, to))) => (to, from + length),
+            _ => (0.0, length),
+        };
+        gaps.iter()
+            .skip(usize::from(self.closed))
+            .for_each(|(from, to)| {
+                if *from > cursor {
+                    kept.push((cursor, *from));
+                }
+                cursor = cursor.max(*to);
+            });
+        if end > cursor {
+            kept.push((cursor, end));
+        }
+        kept
+    }
+}
+
+//How far along a segment its closest point to `point` is, from 0 to 1
+fn closest_on(segment: &[Point], point: Point) -> f32 {
+    let step = segment[1] - segment[0];
+    let length = step.dot(step);
+    match length > 0.0 {
+        true => ((point - segment[0]).dot(step) / length).clamp(0.0, 1.0),
+        false => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Glyph, GlyphStyle};
+
+    fn scene(phrase: &str) -> Scene {
+        Scene::new(&Glyph::new(phrase).unwrap(), &GlyphStyle::default())
+    }
+
+    //Islands left in the material once every cut is made
+    fn islands(scene: &Scene, options: &DxfOptions, cuts: &[(usize, Cut)]) -> usize {
+        let sheet = Sheet::new(scene, options).unwrap();
+        Grid::new(&sheet, cell_size(&sheet, options), cuts, &[]).islands(options.bridge_width / 4.0)
+    }
+
+    #[test]
+    fn enclosed_rings_are_bridged() {
+        let scene = scene("o");
+        let options = DxfOptions::default();
+        //The inside of the ring falls out without bridges
+        let sheet = Sheet::new(&scene, &options).unwrap();
+        let ring = Stroke::new(sheet.ring_line(&scene.rings[0]), true, 0);
+        let slot = slots(&[ring], &[vec![]], sheet.half_width);
+        assert!(islands(&scene, &options, &slot) > 0);
+
+        let (cuts, bridges) = bridged_cuts(&scene, &options, MAX_ROUNDS).unwrap();
+        assert!(bridges >= 1);
+        assert_eq!(islands(&scene, &options, &cuts), 0);
+    }
+
+    #[test]
+    fn open_cuts_need_no_bridges() {
+        let mut scene = scene("hello");
+        scene.rings.clear();
+        scene.nodes.clear();
+        scene.edges.truncate(1);
+        let options = DxfOptions::default();
+        let (cuts, bridges) = bridged_cuts(&scene, &options, MAX_ROUNDS).unwrap();
+        assert_eq!(bridges, 0);
+        assert_eq!(cuts.len(), 1);
+    }
+
+    #[test]
+    fn islands_left_after_the_last_round_are_an_error() {
+        let Err(error) = bridged_cuts(&scene("o"), &DxfOptions::default(), 0) else {
+            panic!("the ring was bridged without a single round");
+        };
+        assert!(error.contains("still enclosed after 0 rounds"), "{error}");
+    }
+}
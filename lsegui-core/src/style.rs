@@ -0,0 +1,429 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{EdgeKind, EdgeStyle, Glyph, Theme};
+
+/// The palette colour schemes pick from unless given one of their own,
+/// chosen to stay apart for colour blind eyes.
+pub const DEFAULT_PALETTE: [Color; 7] = [
+    Color::rgb(230, 159, 0),
+    Color::rgb(86, 180, 233),
+    Color::rgb(0, 158, 115),
+    Color::rgb(240, 228, 66),
+    Color::rgb(0, 114, 178),
+    Color::rgb(213, 94, 0),
+    Color::rgb(204, 121, 167),
+];
+
+/// An sRGB colour with straight alpha, saved as `#rrggbb` or `#rrggbbaa`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    pub const TRANSPARENT: Color = Color::rgba(0, 0, 0, 0);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b, a: 255 }
+    }
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Color { r, g, b, a }
+    }
+
+    /// The colour as `#rrggbb`, ignoring alpha.
+    pub fn to_hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+
+    /// Reads `#rrggbb` or `#rrggbbaa`, with or without the `#`.
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.trim().trim_start_matches('#');
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        match hex.len() {
+            6 => Some(Color::rgb(channel(0)?, channel(2)?, channel(4)?)),
+            8 => Some(Color::rgba(
+                channel(0)?,
+                channel(2)?,
+                channel(4)?,
+                channel(6)?,
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn opacity(self) -> f32 {
+        self.a as f32 / 255.0
+    }
+
+    /// The colour `t` of the way from `self` to `other`.
+    pub fn lerp(self, other: Color, t: f32) -> Self {
+        let mix = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * t).round() as u8;
+        Color::rgba(
+            mix(self.r, other.r),
+            mix(self.g, other.g),
+            mix(self.b, other.b),
+            mix(self.a, other.a),
+        )
+    }
+}
+
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.a {
+            255 => self.to_hex(),
+            a => format!("{}{a:02x}", self.to_hex()),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        Color::from_hex(&hex)
+            .ok_or_else(|| serde::de::Error::custom(format!("`{hex}` is not a #rrggbb colour")))
+    }
+}
+
+/// The font letter labels are written in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LabelFont {
+    #[default]
+    Monospace,
+    Proportional,
+}
+
+impl LabelFont {
+    pub const ALL: [LabelFont; 2] = [LabelFont::Monospace, LabelFont::Proportional];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LabelFont::Monospace => "monospace",
+            LabelFont::Proportional => "proportional",
+        }
+    }
+
+    /// The generic CSS font family closest to the font.
+    pub fn css_family(self) -> &'static str {
+        match self {
+            LabelFont::Monospace => "monospace",
+            LabelFont::Proportional => "sans-serif",
+        }
+    }
+}
+
+/// What the colours of a glyph's rings, nodes and edges follow.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ColorScheme {
+    /// The colours of the style, the same for every word.
+    #[default]
+    Plain,
+    /// A colour of the palette for every word, its ring, letters and edges.
+    Word,
+    /// A colour of the palette for every letter of the alphabet.
+    Letter,
+    /// Letters shade through the palette from A to Z.
+    Alphabet,
+    /// Edges take the colour of the letter they start from.
+    Source,
+}
+
+impl ColorScheme {
+    pub const ALL: [ColorScheme; 5] = [
+        ColorScheme::Plain,
+        ColorScheme::Word,
+        ColorScheme::Letter,
+        ColorScheme::Alphabet,
+        ColorScheme::Source,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorScheme::Plain => "plain",
+            ColorScheme::Word => "by word",
+            ColorScheme::Letter => "by letter",
+            ColorScheme::Alphabet => "alphabet",
+            ColorScheme::Source => "by source",
+        }
+    }
+
+    /// Looks a scheme up by its [`ColorScheme::name`], with `-` or `_` for spaces.
+    pub fn named(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase().replace(['-', '_'], " ");
+        ColorScheme::ALL
+            .into_iter()
+            .find(|scheme| scheme.name() == name)
+    }
+}
+
+/// How a glyph is drawn: colours, stroke widths and sizes in canvas units.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GlyphStyle {
+    pub background: Color,
+    pub ring_color: Color,
+    pub ring_width: f32,
+    pub node_color: Color,
+    pub node_radius: f32,
+    pub edge_color: Color,
+    /// Colour of the edges between letters of different words.
+    pub between_color: Color,
+    pub edge_width: f32,
+    /// How edges are drawn unless the glyph gives one its own style.
+    pub edge_style: EdgeStyle,
+    pub curve_size: f32,
+    pub loop_size: f32,
+    pub label_color: Color,
+    pub label_size: f32,
+    pub label_font: LabelFont,
+    pub labels: bool,
+    /// What decides the colour of each ring, node and edge.
+    pub color_scheme: ColorScheme,
+    /// The colours picked from by every scheme but [`ColorScheme::Plain`].
+    pub palette: Vec<Color>,
+}
+
+impl Default for GlyphStyle {
+    fn default() -> Self {
+        GlyphStyle::dark()
+    }
+}
+
+impl GlyphStyle {
+    /// Names accepted by [`GlyphStyle::named`].
+    pub const NAMES: &'static [&'static str] = Theme::NAMES;
+
+    /// Looks up the style of a built in [`Theme`] by name.
+    pub fn named(name: &str) -> Option<Self> {
+        Theme::named(name).map(|theme| theme.glyph_style())
+    }
+
+    /// The colours of the app's dark theme.
+    pub fn dark() -> Self {
+        GlyphStyle {
+            background: Color::rgb(27, 27, 27),
+            ring_color: Color::WHITE,
+            ring_width: 3.0,
+            node_color: Color::rgb(180, 180, 180),
+            node_radius: 3.0,
+            edge_color: Color::rgb(180, 180, 180),
+            between_color: Color::rgb(110, 160, 220),
+            edge_width: 2.0,
+            edge_style: EdgeStyle::Curve,
+            curve_size: 20.0,
+            loop_size: 3.0,
+            label_color: Color::rgb(180, 180, 180),
+            label_size: 3.0,
+            label_font: LabelFont::Monospace,
+            labels: true,
+            color_scheme: ColorScheme::Plain,
+            palette: DEFAULT_PALETTE.to_vec(),
+        }
+    }
+
+    /// The colour of the ring of the `word`th word.
+    pub fn ring_color_of(&self, word: usize) -> Color {
+        match self.color_scheme {
+            ColorScheme::Word => self.palette_color(word),
+            _ => None,
+        }
+        .unwrap_or(self.ring_color)
+    }
+
+    /// The colour of the node of `letter` in the `word`th word.
+    pub fn node_color_of(&self, word: usize, letter: char) -> Color {
+        match self.color_scheme {
+            ColorScheme::Plain => None,
+            ColorScheme::Word => self.palette_color(word),
+            ColorScheme::Letter | ColorScheme::Source => {
+                alphabet_index(letter).and_then(|i| self.palette_color(i))
+            }
+            ColorScheme::Alphabet => self.gradient_color(letter),
+        }
+        .unwrap_or(self.node_color)
+    }
+
+    /// The colour of an edge starting from `letter` in the `word`th word.
+    pub fn edge_color_of(&self, word: usize, letter: char, kind: EdgeKind) -> Color {
+        let plain = match kind {
+            EdgeKind::Within => self.edge_color,
+            EdgeKind::Between => self.between_color,
+        };
+        match self.color_scheme {
+            ColorScheme::Word => self.palette_color(word),
+            ColorScheme::Source => alphabet_index(letter).and_then(|i| self.palette_color(i)),
+            _ => None,
+        }
+        .unwrap_or(plain)
+    }
+
+    /// The colour of the label of `letter` in the `word`th word, the same as
+    /// its node unless the glyph is plain.
+    pub fn label_color_of(&self, word: usize, letter: char) -> Color {
+        match self.color_scheme {
+            ColorScheme::Plain => self.label_color,
+            _ => self.node_color_of(word, letter),
+        }
+    }
+
+    /// What each colour stands for in `glyph`, empty for [`ColorScheme::Plain`].
+    pub fn legend(&self, glyph: &Glyph) -> Vec<(String, Color)> {
+        let circles = &glyph.circles.circle_list;
+        match self.color_scheme {
+            ColorScheme::Plain => vec![],
+            ColorScheme::Word => circles
+                .iter()
+                .enumerate()
+                .map(|(word, circle)| (circle.word.word.clone(), self.ring_color_of(word)))
+                .collect(),
+            _ => circles
+                .iter()
+                .flat_map(|circle| circle.word.word.chars())
+                .map(|letter| letter.to_ascii_uppercase())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .map(|letter| (letter.to_string(), self.node_color_of(0, letter)))
+                .collect(),
+        }
+    }
+
+    //Palette colours repeat once there are more words or letters than colours
+    fn palette_color(&self, i: usize) -> Option<Color> {
+        (!self.palette.is_empty()).then(|| self.palette[i % self.palette.len()])
+    }
+
+    fn gradient_color(&self, letter: char) -> Option<Color> {
+        let t = alphabet_index(letter)? as f32 / 25.0;
+        match self.palette.as_slice() {
+            [] => None,
+            [only] => Some(*only),
+            palette => {
+                let scaled = t * (palette.len() - 1) as f32;
+                let i = (scaled as usize).min(palette.len() - 2);
+                Some(palette[i].lerp(palette[i + 1], scaled - i as f32))
+            }
+        }
+    }
+}
+
+fn alphabet_index(letter: char) -> Option<usize> {
+    letter
+        .is_ascii_alphabetic()
+        .then(|| (letter.to_ascii_uppercase() as u8 - b'A') as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::Scene;
+
+    fn styled(color_scheme: ColorScheme) -> GlyphStyle {
+        GlyphStyle {
+            color_scheme,
+            ..GlyphStyle::default()
+        }
+    }
+
+    fn colors(scene: &Scene) -> Vec<Color> {
+        scene
+            .rings
+            .iter()
+            .map(|ring| ring.color)
+            .chain(scene.nodes.iter().map(|dot| dot.color))
+            .chain(scene.edges.iter().map(|edge| edge.color))
+            .chain(scene.labels.iter().map(|label| label.color))
+            .collect()
+    }
+
+    #[test]
+    fn schemes_color_alike_things_alike() {
+        let glyph = Glyph::new("hello world").unwrap();
+        ColorScheme::ALL.into_iter().for_each(|scheme| {
+            let style = styled(scheme);
+            let scene = Scene::new(&glyph, &style);
+            //Drawing the phrase again, or laid out again, gives the same colours
+            let again = Scene::new(&Glyph::new("hello world").unwrap(), &style);
+            assert_eq!(colors(&scene), colors(&again), "{}", scheme.name());
+
+            scene.nodes.iter().for_each(|a| {
+                scene.nodes.iter().for_each(|b| match scheme {
+                    ColorScheme::Plain => assert_eq!(a.color, style.node_color),
+                    ColorScheme::Word => assert_eq!(a.word == b.word, a.color == b.color),
+                    _ if a.letter == b.letter => assert_eq!(a.color, b.color, "{}", scheme.name()),
+                    _ => {}
+                });
+            });
+        });
+
+        //Letters are picked by their place in the alphabet, not in the phrase
+        let letter = styled(ColorScheme::Letter);
+        assert_eq!(letter.node_color_of(3, 'A'), DEFAULT_PALETTE[0]);
+        assert_eq!(letter.node_color_of(0, 'h'), DEFAULT_PALETTE[0]);
+        assert_eq!(letter.node_color_of(0, 'C'), DEFAULT_PALETTE[2]);
+        let alphabet = styled(ColorScheme::Alphabet);
+        assert_eq!(alphabet.node_color_of(0, 'A'), DEFAULT_PALETTE[0]);
+        assert_eq!(alphabet.node_color_of(0, 'Z'), DEFAULT_PALETTE[6]);
+        assert_eq!(
+            styled(ColorScheme::Word).ring_color_of(8),
+            DEFAULT_PALETTE[1]
+        );
+    }
+
+    #[test]
+    fn legends_match_the_drawn_colors() {
+        let glyph = Glyph::new("hello world").unwrap();
+        let letters = glyph
+            .nodes()
+            .map(|(node, letter, _)| (node, letter))
+            .collect::<HashMap<_, _>>();
+        ColorScheme::ALL.into_iter().for_each(|scheme| {
+            let style = styled(scheme);
+            let scene = Scene::new(&glyph, &style);
+            let legend = style.legend(&glyph);
+            let name = scheme.name();
+            match scheme {
+                ColorScheme::Plain => assert!(legend.is_empty()),
+                ColorScheme::Word => {
+                    assert_eq!(legend.len(), scene.rings.len());
+                    scene.rings.iter().for_each(|ring| {
+                        let (word, color) = &legend[ring.word];
+                        assert_eq!(*word, glyph.phrase.phrase_words[ring.word].word);
+                        assert_eq!(*color, ring.color);
+                    });
+                    scene
+                        .nodes
+                        .iter()
+                        .for_each(|dot| assert_eq!(dot.color, legend[dot.word].1));
+                }
+                _ => {
+                    let legend = legend
+                        .iter()
+                        .map(|(letter, color)| (letter.chars().next().unwrap(), *color))
+                        .collect::<HashMap<_, _>>();
+                    assert_eq!(legend.len(), "HELOWRD".len(), "{name}");
+                    scene.nodes.iter().for_each(|dot| {
+                        assert_eq!(dot.color, legend[&dot.letter], "{name} {}", dot.letter);
+                    });
+                    if scheme == ColorScheme::Source {
+                        scene
+                            .edges
+                            .iter()
+                            .zip(glyph.edges())
+                            .for_each(|(edge, (source, ..))| {
+                                assert_eq!(edge.color, legend[&letters[&source]], "{name}");
+                            });
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,137 @@
+use std::fmt::Write;
+
+use crate::number::num;
+use crate::{Color, EdgePath, Scene};
+
+//Decimals numbers are written with
+const DECIMALS: usize = 2;
+
+/// Writes a scene as an SVG document with real `<circle>` and `<path>` elements.
+pub fn to_svg(scene: &Scene) -> String {
+    let bounds = scene.bounds;
+    let mut svg = String::new();
+
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" width="{}" height="{}">"#,
+        num(bounds.min.x, DECIMALS),
+        num(bounds.min.y, DECIMALS),
+        num(bounds.width(), DECIMALS),
+        num(bounds.height(), DECIMALS),
+        num(bounds.width(), DECIMALS),
+        num(bounds.height(), DECIMALS),
+    )
+    .unwrap();
+
+    if scene.background.a > 0 {
+        writeln!(
+            svg,
+            r#"  <rect x="{}" y="{}" width="{}" height="{}"{}/>"#,
+            num(bounds.min.x, DECIMALS),
+            num(bounds.min.y, DECIMALS),
+            num(bounds.width(), DECIMALS),
+            num(bounds.height(), DECIMALS),
+            paint("fill", scene.background),
+        )
+        .unwrap();
+    }
+
+    writeln!(svg, r#"  <g fill="none" stroke-linecap="round">"#).unwrap();
+    scene.rings.iter().for_each(|ring| {
+        writeln!(
+            svg,
+            r#"    <circle cx="{}" cy="{}" r="{}" stroke-width="{}"{}/>"#,
+            num(ring.center.x, DECIMALS),
+            num(ring.center.y, DECIMALS),
+            num(ring.radius, DECIMALS),
+            num(ring.width, DECIMALS),
+            paint("stroke", ring.color),
+        )
+        .unwrap();
+    });
+    scene.edges.iter().for_each(|edge| {
+        let d = match edge.path {
+            EdgePath::Quadratic([start, control, end]) => format!(
+                "M {} {} Q {} {} {} {}",
+                num(start.x, DECIMALS),
+                num(start.y, DECIMALS),
+                num(control.x, DECIMALS),
+                num(control.y, DECIMALS),
+                num(end.x, DECIMALS),
+                num(end.y, DECIMALS),
+            ),
+            EdgePath::Cubic([start, control1, control2, end]) => format!(
+                "M {} {} C {} {} {} {} {} {}",
+                num(start.x, DECIMALS),
+                num(start.y, DECIMALS),
+                num(control1.x, DECIMALS),
+                num(control1.y, DECIMALS),
+                num(control2.x, DECIMALS),
+                num(control2.y, DECIMALS),
+                num(end.x, DECIMALS),
+                num(end.y, DECIMALS),
+            ),
+        };
+        writeln!(
+            svg,
+            r#"    <path d="{}" stroke-width="{}"{}/>"#,
+            d,
+            num(edge.width, DECIMALS),
+            paint("stroke", edge.color),
+        )
+        .unwrap();
+    });
+    writeln!(svg, "  </g>").unwrap();
+
+    writeln!(svg, "  <g>").unwrap();
+    scene.nodes.iter().for_each(|node| {
+        writeln!(
+            svg,
+            r#"    <circle cx="{}" cy="{}" r="{}"{}/>"#,
+            num(node.center.x, DECIMALS),
+            num(node.center.y, DECIMALS),
+            num(node.radius, DECIMALS),
+            paint("fill", node.color),
+        )
+        .unwrap();
+    });
+    writeln!(svg, "  </g>").unwrap();
+
+    if !scene.labels.is_empty() {
+        writeln!(
+            svg,
+            r#"  <g font-family="{}" text-anchor="middle">"#,
+            scene.label_font.css_family()
+        )
+        .unwrap();
+        scene.labels.iter().for_each(|label| {
+            writeln!(
+                svg,
+                r#"    <text x="{}" y="{}" font-size="{}"{}>{}</text>"#,
+                num(label.pos.x, DECIMALS),
+                num(label.pos.y, DECIMALS),
+                num(label.size, DECIMALS),
+                paint("fill", label.color),
+                label.text,
+            )
+            .unwrap();
+        });
+        writeln!(svg, "  </g>").unwrap();
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+//A fill or stroke attribute, with its opacity when the colour is translucent
+fn paint(attribute: &str, color: Color) -> String {
+    if color.a == 255 {
+        format!(r#" {attribute}="{}""#, color.to_hex())
+    } else {
+        format!(
+            r#" {attribute}="{}" {attribute}-opacity="{}""#,
+            color.to_hex(),
+            num(color.opacity(), DECIMALS)
+        )
+    }
+}
@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Color, GlyphStyle, LabelFont};
+
+/// The colours and sizes a glyph is drawn with, and whether the app around
+/// it is light or dark.
+///
+/// Themes are saved as JSON. Anything a theme file leaves out is taken from
+/// the dark theme, so a file only has to list what it changes.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub name: String,
+    /// Whether the app's own widgets are drawn light on dark.
+    pub dark: bool,
+    pub background: Color,
+    pub ring_color: Color,
+    pub ring_width: f32,
+    pub node_color: Color,
+    pub node_radius: f32,
+    pub edge_color: Color,
+    /// Colour of the edges between letters of different words.
+    pub between_color: Color,
+    pub edge_width: f32,
+    pub label_color: Color,
+    pub label_size: f32,
+    pub label_font: LabelFont,
+}
+
+impl Default for Theme {
+    //What a theme file without a name is called
+    fn default() -> Self {
+        Theme {
+            name: String::from("custom"),
+            ..Theme::dark()
+        }
+    }
+}
+
+impl Theme {
+    /// Names accepted by [`Theme::named`].
+    pub const NAMES: &'static [&'static str] = &["dark", "light", "high-contrast"];
+
+    /// Looks a built in theme up by name.
+    pub fn named(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().replace(['_', ' '], "-").as_str() {
+            "dark" => Some(Theme::dark()),
+            "light" => Some(Theme::light()),
+            "high-contrast" => Some(Theme::high_contrast()),
+            _ => None,
+        }
+    }
+
+    /// The colours of the app's dark theme.
+    pub fn dark() -> Self {
+        let style = GlyphStyle::dark();
+        Theme {
+            name: String::from("dark"),
+            dark: true,
+            background: style.background,
+            ring_color: style.ring_color,
+            ring_width: style.ring_width,
+            node_color: style.node_color,
+            node_radius: style.node_radius,
+            edge_color: style.edge_color,
+            between_color: style.between_color,
+            edge_width: style.edge_width,
+            label_color: style.label_color,
+            label_size: style.label_size,
+            label_font: style.label_font,
+        }
+    }
+
+    /// Dark lines on an off-white canvas.
+    pub fn light() -> Self {
+        Theme {
+            name: String::from("light"),
+            dark: false,
+            background: Color::rgb(248, 248, 244),
+            ring_color: Color::rgb(30, 30, 30),
+            node_color: Color::rgb(70, 70, 70),
+            edge_color: Color::rgb(70, 70, 70),
+            between_color: Color::rgb(40, 100, 180),
+            label_color: Color::rgb(70, 70, 70),
+            ..Theme::dark()
+        }
+    }
+
+    /// Bright, thick lines on black.
+    pub fn high_contrast() -> Self {
+        Theme {
+            name: String::from("high-contrast"),
+            dark: true,
+            background: Color::BLACK,
+            ring_color: Color::WHITE,
+            ring_width: 4.0,
+            node_color: Color::WHITE,
+            node_radius: 4.0,
+            edge_color: Color::WHITE,
+            between_color: Color::rgb(255, 215, 0),
+            edge_width: 3.0,
+            label_color: Color::rgb(255, 255, 0),
+            label_size: 4.0,
+            ..Theme::dark()
+        }
+    }
+
+    /// The style a glyph is drawn with in this theme, with everything the
+    /// theme doesn't cover as in [`GlyphStyle::dark`].
+    pub fn glyph_style(&self) -> GlyphStyle {
+        GlyphStyle {
+            background: self.background,
+            ring_color: self.ring_color,
+            ring_width: self.ring_width,
+            node_color: self.node_color,
+            node_radius: self.node_radius,
+            edge_color: self.edge_color,
+            between_color: self.between_color,
+            edge_width: self.edge_width,
+            label_color: self.label_color,
+            label_size: self.label_size,
+            label_font: self.label_font,
+            ..GlyphStyle::dark()
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("themes should always serialize")
+    }
+
+    pub fn from_json(source: &str) -> Result<Self, String> {
+        //Serde would also read the fields in order from an array, taking [] for the dark theme
+        serde_json::from_str::<serde_json::Value>(source)
+            .and_then(|value| match value.is_object() {
+                true => serde_json::from_value(value),
+                false => Err(serde::de::Error::custom("expected an object of settings")),
+            })
+            .map_err(|error| format!("not a readable theme: {error}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConnectionTable, Glyph, History, Project};
+
+    //A built in theme with changes made to it, still going by the built in name
+    fn edited_theme() -> Theme {
+        Theme {
+            ring_color: Color::rgb(200, 40, 90),
+            ring_width: 4.5,
+            label_font: LabelFont::Proportional,
+            ..Theme::light()
+        }
+    }
+
+    #[test]
+    fn theme_files_round_trip() {
+        let renamed = Theme {
+            name: String::from("paper"),
+            ..edited_theme()
+        };
+        [
+            Theme::dark(),
+            Theme::light(),
+            Theme::high_contrast(),
+            edited_theme(),
+            renamed,
+        ]
+        .into_iter()
+        .for_each(|theme| assert_eq!(Theme::from_json(&theme.to_json()), Ok(theme)));
+
+        //Whatever a file leaves out comes from the dark theme
+        let partial = Theme::from_json(r##"{"ring_color": "#102030", "ring_width": 5}"##).unwrap();
+        assert_eq!(
+            partial,
+            Theme {
+                name: String::from("custom"),
+                ring_color: Color::rgb(16, 32, 48),
+                ring_width: 5.0,
+                ..Theme::dark()
+            }
+        );
+    }
+
+    #[test]
+    fn malformed_themes_are_rejected() {
+        [
+            "",
+            "dark",
+            "[]",
+            r#"["dark"]"#,
+            r#"{"name": "cut off""#,
+            r#"{"ring_color": "red"}"#,
+            r##"{"ring_color": "#12345"}"##,
+            r#"{"ring_width": "wide"}"#,
+            r#"{"label_font": "comic"}"#,
+            r#"{"dark": "yes"}"#,
+        ]
+        .into_iter()
+        .for_each(|source| {
+            let error = Theme::from_json(source).unwrap_err();
+            assert!(
+                error.starts_with("not a readable theme"),
+                "{source}: {error}"
+            );
+        });
+    }
+
+    #[test]
+    fn edited_themes_survive_saves_and_undo() {
+        let glyph = Glyph::new("hello").unwrap();
+        let rules = ConnectionTable::default();
+        let project = Project::new("hello", &glyph, "default", &rules, &edited_theme());
+        //Only saving the name would bring the unedited light theme back
+        let reopened = Project::from_json(&project.to_json()).unwrap();
+        assert_eq!(reopened.saved_theme(), Some(edited_theme()));
+
+        let mut history = History::default();
+        history.record(project);
+        let current = Project::new("hello", &glyph, "default", &rules, &Theme::dark());
+        let snapshot = history.undo(current).unwrap();
+        assert_eq!(snapshot.saved_theme(), Some(edited_theme()));
+    }
+}
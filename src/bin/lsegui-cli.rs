@@ -0,0 +1,635 @@
+//! Renders Gall glyphs to files from the command line.
+//!
+//! A binary of its own rather than part of the app, which has no console on
+//! Windows to print to.
+
+use std::collections::HashSet;
+use std::io::{BufRead, Read};
+
+use lsegui_core::{
+    to_dot, to_dxf, to_gcode, to_graph_json, to_graphml, to_hpgl, to_obj, to_png, to_stencil_dxf,
+    to_stencil_svg, to_stl, to_svg, BaseShape, Color, ColorScheme, ConnectionTable, DxfOptions,
+    EdgeStyle, ExtrudeOptions, Glyph, GlyphStyle, PlotOptions, RasterOptions, Scene, Theme,
+};
+
+const USAGE: &str = "\
+Render Gall glyphs without opening a window.
+
+Usage: lsegui-cli [render] [PHRASE] [OPTIONS]
+
+Phrases are read one per line from --input, or from stdin when no PHRASE is given.
+Phrases that can't be drawn are reported and skipped, and the exit code is 1.
+The render command can be left out, give it to render the word render itself.
+
+Options:
+  -o, --output PATH     Where to write the glyph. With several phrases, {n} is
+                        replaced by the phrase's number and {phrase} by the phrase.
+                        Phrases written to the same file get -2, -3, ... added
+                        [default: {phrase}.<format>]
+  -i, --input FILE      Read phrases from FILE, one per line (- for stdin)
+  -f, --format FORMAT   svg, png, gcode, hpgl, dxf, stl, obj, or the connection
+                        graph as dot, graphml or json
+                        [default: from the output extension, else svg]
+  -s, --size SIZE       PNG size as WIDTHxHEIGHT or a single number [default: 2048]
+      --paper PAPER     Plotter paper as a name (a4, letter, ...) or WIDTHxHEIGHT
+                        in mm [default: a4]
+      --margin MM       Space left free around the plotted glyph [default: 15]
+      --feed SPEED      Plotter drawing speed in mm per minute [default: 1500]
+      --width MM        Width of the cut DXF or printed STL and OBJ glyph
+                        [default: 200 cut, 80 printed]
+      --edge-width MM   Width of the slots cut for the edges [default: 1.5]
+      --kerf MM         Width the cutter burns away [default: 0.2]
+      --stencil         Cut the SVG or DXF as a stencil, with bridges holding
+                        every enclosed island in place
+      --bridge MM       Width of the stencil bridges [default: 2]
+      --bridges COUNT   Bridges across every slot that needs them [default: 1]
+      --height MM       How far printed strokes stand above the base [default: 2]
+      --base SHAPE      none, disc or plate under the printed glyph [default: disc]
+      --base-height MM  Thickness of the printed base [default: 2]
+  -t, --theme THEME     dark, light, high-contrast or a theme file [default: dark]
+  -e, --edges STYLE     curve, chord, inward-arc or outward-arc [default: curve]
+  -c, --colors SCHEME   plain, by-word, by-letter, alphabet or by-source [default: plain]
+      --palette COLORS  Comma separated #rrggbb colours for the colour scheme
+      --transparent     Leave the PNG background transparent
+      --rules FILE      Connection table to build the glyphs with
+      --reach WORDS     Also connect letters into this many words to either side
+  -h, --help            Print this help";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Svg,
+    Png,
+    Gcode,
+    Hpgl,
+    Dxf,
+    Stl,
+    Obj,
+    Dot,
+    Graphml,
+    Json,
+}
+
+impl Format {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "svg" => Some(Format::Svg),
+            "png" => Some(Format::Png),
+            "gcode" | "nc" => Some(Format::Gcode),
+            "hpgl" | "plt" => Some(Format::Hpgl),
+            "dxf" => Some(Format::Dxf),
+            "stl" => Some(Format::Stl),
+            "obj" => Some(Format::Obj),
+            "dot" | "gv" => Some(Format::Dot),
+            "graphml" => Some(Format::Graphml),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Svg => "svg",
+            Format::Png => "png",
+            Format::Gcode => "gcode",
+            Format::Hpgl => "hpgl",
+            Format::Dxf => "dxf",
+            Format::Stl => "stl",
+            Format::Obj => "obj",
+            Format::Dot => "dot",
+            Format::Graphml => "graphml",
+            Format::Json => "json",
+        }
+    }
+}
+
+struct Options {
+    phrase: Option<String>,
+    input: Option<String>,
+    output: Option<String>,
+    format: Option<Format>,
+    raster: RasterOptions,
+    plot: PlotOptions,
+    cut: DxfOptions,
+    stencil: bool,
+    solid: ExtrudeOptions,
+    style: GlyphStyle,
+    rules: ConnectionTable,
+    //Applied after the table, which may be given later on the command line
+    reach: Option<usize>,
+    //Put together into the style once every option has been read
+    theme: Theme,
+    edge_style: EdgeStyle,
+    color_scheme: ColorScheme,
+    palette: Option<Vec<Color>>,
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    std::process::exit(run(&args));
+}
+
+//Runs with the arguments after the program name and returns the exit code
+fn run(args: &[String]) -> i32 {
+    let options = match parse_args(args) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{USAGE}");
+            return 0;
+        }
+        Err(error) => {
+            eprintln!("error: {error}\n\n{USAGE}");
+            return 2;
+        }
+    };
+
+    match render_all(&options) {
+        Ok(()) => 0,
+        Err(error) => {
+            eprintln!("error: {error}");
+            1
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Option<Options>, String> {
+    let mut options = Options {
+        phrase: None,
+        input: None,
+        output: None,
+        format: None,
+        raster: RasterOptions::default(),
+        plot: PlotOptions::default(),
+        cut: DxfOptions::default(),
+        stencil: false,
+        solid: ExtrudeOptions::default(),
+        style: GlyphStyle::dark(),
+        rules: ConnectionTable::default(),
+        reach: None,
+        theme: Theme::dark(),
+        edge_style: EdgeStyle::default(),
+        color_scheme: ColorScheme::default(),
+        palette: None,
+    };
+
+    //`lsegui-cli render PHRASE` reads the same as `lsegui-cli PHRASE`
+    let args = match args.split_first() {
+        Some((command, rest)) if command == "render" => rest,
+        _ => args,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("{arg} needs a value"))
+                .map(String::as_str)
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-o" | "--output" => options.output = Some(value()?.to_string()),
+            "-i" | "--input" => options.input = Some(value()?.to_string()),
+            "-f" | "--format" => {
+                let name = value()?;
+                options.format = Some(
+                    Format::from_name(name).ok_or_else(|| format!("unknown format `{name}`"))?,
+                );
+            }
+            "-s" | "--size" => {
+                let (width, height) = parse_size(value()?)?;
+                options.raster.width = width;
+                options.raster.height = height;
+            }
+            "--paper" => {
+                let paper = value()?;
+                let (width, height) =
+                    PlotOptions::paper_named(paper).map_or_else(|| parse_paper(paper), Ok)?;
+                options.plot.paper_width = width;
+                options.plot.paper_height = height;
+            }
+            "--margin" => options.plot.margin = parse_millimetres(value()?)?,
+            "--feed" => options.plot.feed_rate = parse_millimetres(value()?)?,
+            "--width" => {
+                let width = parse_millimetres(value()?)?;
+                options.cut.width = width;
+                options.solid.width = width;
+            }
+            "--edge-width" => options.cut.edge_width = parse_millimetres(value()?)?,
+            "--kerf" => options.cut.kerf = parse_millimetres(value()?)?,
+            "--stencil" => options.stencil = true,
+            "--bridge" => options.cut.bridge_width = parse_millimetres(value()?)?,
+            "--bridges" => {
+                let count = value()?;
+                options.cut.bridges = count
+                    .parse()
+                    .map_err(|_| format!("invalid bridge count `{count}`, expected a number"))?;
+            }
+            "--height" => options.solid.height = parse_millimetres(value()?)?,
+            "--base" => {
+                let name = value()?;
+                options.solid.base = BaseShape::named(name).ok_or_else(|| {
+                    let names = BaseShape::ALL.map(BaseShape::name);
+                    format!(
+                        "unknown base `{name}`, expected one of: {}",
+                        names.join(", ")
+                    )
+                })?;
+            }
+            "--base-height" => options.solid.base_height = parse_millimetres(value()?)?,
+            "-t" | "--theme" => {
+                let name = value()?;
+                options.theme = match Theme::named(name) {
+                    Some(theme) => theme,
+                    None if std::path::Path::new(name).is_file() => {
+                        let source = std::fs::read_to_string(name)
+                            .map_err(|error| format!("could not read {name}: {error}"))?;
+                        Theme::from_json(&source).map_err(|error| format!("{name}: {error}"))?
+                    }
+                    None => {
+                        return Err(format!(
+                            "unknown theme `{name}`, expected a theme file or one of: {}",
+                            Theme::NAMES.join(", ")
+                        ))
+                    }
+                };
+            }
+            "-e" | "--edges" => {
+                let name = value()?;
+                options.edge_style = EdgeStyle::named(name).ok_or_else(|| {
+                    let names = EdgeStyle::ALL.map(|style| style.name().replace(' ', "-"));
+                    format!(
+                        "unknown edge style `{name}`, expected one of: {}",
+                        names.join(", ")
+                    )
+                })?;
+            }
+            "-c" | "--colors" => {
+                let name = value()?;
+                options.color_scheme = ColorScheme::named(name).ok_or_else(|| {
+                    let names = ColorScheme::ALL.map(|scheme| scheme.name().replace(' ', "-"));
+                    format!(
+                        "unknown colour scheme `{name}`, expected one of: {}",
+                        names.join(", ")
+                    )
+                })?;
+            }
+            "--palette" => {
+                options.palette = Some(
+                    value()?
+                        .split(',')
+                        .map(|hex| {
+                            Color::from_hex(hex)
+                                .ok_or_else(|| format!("invalid colour `{hex}`, expected #rrggbb"))
+                        })
+                        .collect::<Result<_, _>>()?,
+                );
+            }
+            "--transparent" => options.raster.transparent = true,
+            "--rules" => {
+                let path = value()?;
+                let source = std::fs::read_to_string(path)
+                    .map_err(|error| format!("could not read {path}: {error}"))?;
+                options.rules = ConnectionTable::parse(&source).map_err(|errors| {
+                    errors
+                        .iter()
+                        .map(|error| format!("{path}: {error}"))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                })?;
+            }
+            "--reach" => {
+                let words = value()?;
+                options.reach = Some(
+                    words
+                        .parse()
+                        .map_err(|_| format!("invalid reach `{words}`, expected a number"))?,
+                );
+            }
+            flag if flag.starts_with('-') && flag != "-" => {
+                return Err(format!("unknown option `{flag}`"))
+            }
+            phrase if options.phrase.is_none() => options.phrase = Some(phrase.to_string()),
+            extra => return Err(format!("unexpected argument `{extra}`")),
+        }
+    }
+
+    if options.phrase.is_some() && options.input.is_some() {
+        return Err(String::from("give either a PHRASE or --input, not both"));
+    }
+    if let Some(reach) = options.reach {
+        options.rules.set_reach(reach);
+    }
+    options.style = GlyphStyle {
+        edge_style: options.edge_style,
+        color_scheme: options.color_scheme,
+        palette: options
+            .palette
+            .take()
+            .unwrap_or_else(|| options.style.palette.clone()),
+        ..options.theme.glyph_style()
+    };
+
+    Ok(Some(options))
+}
+
+//Either a single number for a square image or WIDTHxHEIGHT
+fn parse_size(size: &str) -> Result<(u32, u32), String> {
+    let invalid = || format!("invalid size `{size}`");
+    let (width, height) = match size.split_once(['x', 'X']) {
+        Some((width, height)) => (width, height),
+        None => (size, size),
+    };
+    let width = width.trim().parse::<u32>().map_err(|_| invalid())?;
+    let height = height.trim().parse::<u32>().map_err(|_| invalid())?;
+    if width == 0 || height == 0 {
+        return Err(invalid());
+    }
+    Ok((width, height))
+}
+
+//A paper size in millimetres as WIDTHxHEIGHT
+fn parse_paper(paper: &str) -> Result<(f32, f32), String> {
+    let (width, height) = paper.split_once(['x', 'X']).ok_or_else(|| {
+        let names = PlotOptions::PAPERS
+            .iter()
+            .map(|(name, _, _)| *name)
+            .collect::<Vec<_>>();
+        format!(
+            "unknown paper `{paper}`, expected WIDTHxHEIGHT in mm or one of: {}",
+            names.join(", ")
+        )
+    })?;
+    Ok((parse_millimetres(width)?, parse_millimetres(height)?))
+}
+
+fn parse_millimetres(value: &str) -> Result<f32, String> {
+    value
+        .trim()
+        .parse::<f32>()
+        .ok()
+        .filter(|value| value.is_finite() && *value >= 0.0)
+        .ok_or_else(|| format!("invalid length `{value}`"))
+}
+
+fn read_phrases(options: &Options) -> Result<Vec<String>, String> {
+    if let Some(phrase) = &options.phrase {
+        return Ok(vec![phrase.clone()]);
+    }
+
+    let text = match options.input.as_deref() {
+        Some(path) if path != "-" => std::fs::read_to_string(path)
+            .map_err(|error| format!("could not read {path}: {error}"))?,
+        _ => {
+            let mut text = String::new();
+            std::io::stdin()
+                .lock()
+                .read_to_string(&mut text)
+                .map_err(|error| format!("could not read stdin: {error}"))?;
+            text
+        }
+    };
+
+    Ok(text
+        .as_bytes()
+        .lines()
+        .map_while(Result::ok)
+        .filter(|line| !line.trim().is_empty())
+        .collect())
+}
+
+fn render_all(options: &Options) -> Result<(), String> {
+    let phrases = read_phrases(options)?;
+    if phrases.is_empty() {
+        return Err(String::from("no phrases to render"));
+    }
+
+    let format = options
+        .format
+        .or_else(|| {
+            options
+                .output
+                .as_deref()
+                .and_then(|output| output.rsplit_once('.'))
+                .and_then(|(_, extension)| Format::from_name(extension))
+        })
+        .unwrap_or(Format::Svg);
+    if options.stencil && !matches!(format, Format::Svg | Format::Dxf) {
+        return Err(format!(
+            "stencils are cut from svg or dxf, not {}",
+            format.extension()
+        ));
+    }
+
+    let pattern = options
+        .output
+        .clone()
+        .unwrap_or_else(|| format!("{{phrase}}.{}", format.extension()));
+    if phrases.len() > 1 && !pattern.contains("{n}") && !pattern.contains("{phrase}") {
+        return Err(format!(
+            "{} phrases would all be written to {pattern}, add {{n}} or {{phrase}} to the output",
+            phrases.len()
+        ));
+    }
+
+    //A phrase that can't be drawn doesn't stop the rest of the batch
+    let mut written = HashSet::new();
+    let failed = phrases
+        .iter()
+        .enumerate()
+        .filter(|(i, phrase)| {
+            let path = pattern
+                .replace("{n}", &(i + 1).to_string())
+                .replace("{phrase}", &file_stem(phrase));
+            let path = unused_path(&path, &written);
+            match render(options, format, phrase, &path) {
+                Ok(()) => {
+                    println!("{phrase} -> {path}");
+                    written.insert(path);
+                    false
+                }
+                Err(error) => {
+                    eprintln!("error: {error}");
+                    true
+                }
+            }
+        })
+        .count();
+    match failed {
+        0 => Ok(()),
+        _ => Err(format!(
+            "{failed} of {} phrases could not be rendered",
+            phrases.len()
+        )),
+    }
+}
+
+fn render(options: &Options, format: Format, phrase: &str, path: &str) -> Result<(), String> {
+    let glyph = Glyph::with_rules(phrase, &options.rules)
+        .map_err(|error| format!("could not draw {phrase}: {error}"))?;
+    let scene = Scene::new(&glyph, &options.style);
+    let bytes = match format {
+        Format::Svg if options.stencil => to_stencil_svg(&scene, &options.cut)?.into_bytes(),
+        Format::Svg => to_svg(&scene).into_bytes(),
+        Format::Png => to_png(&scene, &options.raster)?,
+        Format::Gcode => to_gcode(&scene, &options.plot)?.into_bytes(),
+        Format::Hpgl => to_hpgl(&scene, &options.plot)?.into_bytes(),
+        Format::Dxf if options.stencil => to_stencil_dxf(&scene, &options.cut)?.into_bytes(),
+        Format::Dxf => to_dxf(&scene, &options.cut)?.into_bytes(),
+        Format::Stl => to_stl(&scene, &options.solid)?,
+        Format::Obj => to_obj(&scene, &options.solid)?.into_bytes(),
+        Format::Dot => to_dot(&glyph).into_bytes(),
+        Format::Graphml => to_graphml(&glyph).into_bytes(),
+        Format::Json => to_graph_json(&glyph).into_bytes(),
+    };
+    std::fs::write(path, bytes).map_err(|error| format!("could not write {path}: {error}"))
+}
+
+//The path with -2, -3, ... added before its extension until it isn't one already written,
+//as phrases like "Hello!" and "hello" have the same file name
+fn unused_path(path: &str, written: &HashSet<String>) -> String {
+    let (stem, extension) = match path.rsplit_once('.') {
+        Some((stem, extension)) if !extension.contains(['/', '\\']) => {
+            (stem, format!(".{extension}"))
+        }
+        _ => (path, String::new()),
+    };
+    std::iter::once(path.to_string())
+        .chain((2..).map(|n| format!("{stem}-{n}{extension}")))
+        .find(|path| !written.contains(path))
+        .expect("there is always an unused number")
+}
+
+//A file name friendly version of the phrase
+fn file_stem(phrase: &str) -> String {
+    let stem = phrase
+        .split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_ascii_alphanumeric())
+                .collect::<String>()
+                .to_lowercase()
+        })
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if stem.is_empty() {
+        String::from("glyph")
+    } else {
+        stem
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Options>, String> {
+        parse_args(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>())
+    }
+
+    fn error(args: &[&str]) -> String {
+        match parse(args) {
+            Err(error) => error,
+            Ok(_) => panic!("{args:?} should not parse"),
+        }
+    }
+
+    //A directory of its own for every test, as they run at the same time
+    fn scratch(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("lsegui-cli-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn arguments_are_read() {
+        let options = parse(&["render", "hello world", "-o", "out.png", "-s", "640x480"])
+            .unwrap()
+            .unwrap();
+        assert_eq!(options.phrase.as_deref(), Some("hello world"));
+        assert_eq!(options.output.as_deref(), Some("out.png"));
+        assert_eq!((options.raster.width, options.raster.height), (640, 480));
+
+        let options = parse(&["hello", "--paper", "a3", "--margin", "20", "-f", "gcode"])
+            .unwrap()
+            .unwrap();
+        assert!(options.format == Some(Format::Gcode));
+        assert_eq!(options.plot.paper_width, 297.0);
+        assert_eq!(options.plot.margin, 20.0);
+
+        //Only the first argument can be the command
+        let options = parse(&["render", "render"]).unwrap().unwrap();
+        assert_eq!(options.phrase.as_deref(), Some("render"));
+        assert!(parse(&["--help"]).unwrap().is_none());
+    }
+
+    #[test]
+    fn bad_arguments_are_refused() {
+        assert_eq!(error(&["hello", "-o"]), "-o needs a value");
+        assert_eq!(error(&["--frobnicate"]), "unknown option `--frobnicate`");
+        assert_eq!(error(&["hello", "world"]), "unexpected argument `world`");
+        assert_eq!(error(&["-s", "0x10"]), "invalid size `0x10`");
+        assert_eq!(error(&["--margin", "-3"]), "invalid length `-3`");
+        assert!(error(&["-f", "bmp"]).starts_with("unknown format"));
+        assert!(error(&["--paper", "huge"]).starts_with("unknown paper"));
+        assert!(error(&["-e", "wavy"]).starts_with("unknown edge style"));
+        assert!(error(&["hello", "-i", "phrases.txt"]).contains("not both"));
+    }
+
+    #[test]
+    fn batches_go_on_past_failures() {
+        let dir = scratch("failures");
+        let input = dir.join("phrases.txt");
+        std::fs::write(&input, "hello\n123\n\nworld\n").unwrap();
+        let output = dir.join("{phrase}.svg");
+        let options = parse(&[
+            "-i",
+            input.to_str().unwrap(),
+            "-o",
+            output.to_str().unwrap(),
+        ])
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(
+            render_all(&options).unwrap_err(),
+            "1 of 3 phrases could not be rendered"
+        );
+        assert!(dir.join("hello.svg").is_file());
+        assert!(dir.join("world.svg").is_file());
+        assert!(!dir.join("123.svg").exists());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn phrases_with_the_same_name_are_all_kept() {
+        let dir = scratch("clashes");
+        let input = dir.join("phrases.txt");
+        std::fs::write(&input, "Hello!\nhello\nhe llo\nHELLO\n").unwrap();
+        let output = dir.join("{phrase}.json");
+        let options = parse(&[
+            "-i",
+            input.to_str().unwrap(),
+            "-o",
+            output.to_str().unwrap(),
+        ])
+        .unwrap()
+        .unwrap();
+
+        render_all(&options).unwrap();
+        ["hello.json", "hello-2.json", "he-llo.json", "hello-3.json"]
+            .into_iter()
+            .for_each(|name| assert!(dir.join(name).is_file(), "{name}"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn only_the_extension_is_numbered() {
+        let written = ["out.svg", "out-2.svg", "dir.v2/glyph"]
+            .map(String::from)
+            .into_iter()
+            .collect::<HashSet<_>>();
+        assert_eq!(unused_path("out.svg", &written), "out-3.svg");
+        assert_eq!(unused_path("dir.v2/glyph", &written), "dir.v2/glyph-2");
+        assert_eq!(unused_path("new.svg", &written), "new.svg");
+    }
+}
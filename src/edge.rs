@@ -1,20 +1,110 @@
+use std::collections::HashMap;
 use std::f32::consts::PI;
+use std::sync::Arc;
 
 use egui::{
+    emath::Rot2,
     epaint::{CubicBezierShape, QuadraticBezierShape},
-    Color32, Pos2, Stroke, Vec2,
+    Color32, Pos2, Shape, Stroke, Vec2,
 };
 use petgraph::{matrix_graph::Nullable, stable_graph::IndexType, EdgeType};
 
 use egui_graphs::{DisplayNode, DrawContext, EdgeProps, Node};
 
 use egui_graphs::DisplayEdge;
+use lsegui_core::{EdgeKind, EdgeStyle, Glyph, GlyphStyle, Point};
+
+use crate::{theme, to_point, to_pos2};
+
+//Dashes and gaps of edges between words, on the screen
+const DASH_LENGTH: f32 = 8.0;
+const GAP_LENGTH: f32 = 5.0;
+//Dashes of the other edges while they flow, and how fast they move in points per second
+const FLOW_DASH_LENGTH: f32 = 10.0;
+const FLOW_GAP_LENGTH: f32 = 6.0;
+const FLOW_SPEED: f32 = 24.0;
+//Where the looks of the edges are kept in egui's memory
+const EDGE_LOOKS: &str = "edge looks";
+const EDGE_MARKS: &str = "edge marks";
+
+//What is drawn on top of the edges to show which way they point
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EdgeMarks {
+    //An arrowhead where the edge ends
+    pub arrows: bool,
+    //Dashes running from the source of the edge to its target
+    pub flow: bool,
+}
+
+//The style of an edge, the ring its arcs bow against, its colour and width
+#[derive(Clone, Copy, Debug)]
+pub struct EdgeLook {
+    style: EdgeStyle,
+    ring: Option<(Point, f32)>,
+    color: Option<Color32>,
+    width: f32,
+}
+type EdgeLooks = HashMap<(usize, usize, usize), EdgeLook>;
+
+//Hand the look of every edge to the edge shapes, by source, target and order
+pub fn set_edge_looks(ctx: &egui::Context, glyph: &Glyph, glyph_style: &GlyphStyle) {
+    //The ring, word and letter of every node
+    let nodes = glyph
+        .circles
+        .circle_list
+        .iter()
+        .enumerate()
+        .flat_map(|(word, circle)| {
+            circle
+                .word
+                .word
+                .chars()
+                .zip(circle.word.nodes.iter())
+                .map(move |(letter, node)| (*node, ((circle.center, circle.radius), word, letter)))
+        })
+        .collect::<HashMap<_, _>>();
+    let looks = glyph
+        .edges()
+        .map(|(source, target, order, kind)| {
+            let style = glyph
+                .edge_styles
+                .get(&(source, target, order))
+                .copied()
+                .unwrap_or(glyph_style.edge_style);
+            let source_node = nodes.get(&source);
+            let ring = match kind {
+                EdgeKind::Within => source_node.map(|(ring, _, _)| *ring),
+                EdgeKind::Between => None,
+            };
+            let color = source_node.map(|(_, word, letter)| {
+                theme::color32(glyph_style.edge_color_of(*word, *letter, kind))
+            });
+            let look = EdgeLook {
+                style,
+                ring,
+                color,
+                width: glyph_style.edge_width,
+            };
+            ((source.index(), target.index(), order), look)
+        })
+        .collect::<EdgeLooks>();
+    ctx.data_mut(|data| data.insert_temp(egui::Id::new(EDGE_LOOKS), Arc::new(looks)));
+}
+
+//Hand the marks to draw to the edge shapes
+pub fn set_edge_marks(ctx: &egui::Context, marks: EdgeMarks) {
+    ctx.data_mut(|data| data.insert_temp(egui::Id::new(EDGE_MARKS), marks));
+}
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Debug)]
 pub struct EdgeShape {
     pub order: usize,
     pub selected: bool,
+    //Joins letters of different words, drawn dashed in its own colour
+    pub between: bool,
+    //As of the last time it was drawn, so hit tests follow the drawn curve
+    pub look: EdgeLook,
 
     pub width: f32,
     pub tip_size: f32,
@@ -23,11 +113,18 @@ pub struct EdgeShape {
     pub loop_size: f32,
 }
 
-impl<E: Clone> From<EdgeProps<E>> for EdgeShape {
-    fn from(edge: EdgeProps<E>) -> Self {
+impl From<EdgeProps<EdgeKind>> for EdgeShape {
+    fn from(edge: EdgeProps<EdgeKind>) -> Self {
         Self {
             order: edge.order,
             selected: edge.selected,
+            between: edge.payload == EdgeKind::Between,
+            look: EdgeLook {
+                style: EdgeStyle::default(),
+                ring: None,
+                color: None,
+                width: 2.,
+            },
 
             width: 2.,
             tip_size: 15.,
@@ -38,13 +135,13 @@ impl<E: Clone> From<EdgeProps<E>> for EdgeShape {
     }
 }
 
-impl<N: Clone, E: Clone, Ty: EdgeType, Ix: IndexType, D: DisplayNode<N, E, Ty, Ix>>
-    DisplayEdge<N, E, Ty, Ix, D> for EdgeShape
+impl<N: Clone, Ty: EdgeType, Ix: IndexType, D: DisplayNode<N, EdgeKind, Ty, Ix>>
+    DisplayEdge<N, EdgeKind, Ty, Ix, D> for EdgeShape
 {
     fn is_inside(
         &self,
-        start: &Node<N, E, Ty, Ix, D>,
-        end: &Node<N, E, Ty, Ix, D>,
+        start: &Node<N, EdgeKind, Ty, Ix, D>,
+        end: &Node<N, EdgeKind, Ty, Ix, D>,
         pos: egui::Pos2,
     ) -> bool {
         if start.id() == end.id() {
@@ -56,60 +153,91 @@ impl<N: Clone, E: Clone, Ty: EdgeType, Ix: IndexType, D: DisplayNode<N, E, Ty, I
 
     fn shapes(
         &mut self,
-        start: &Node<N, E, Ty, Ix, D>,
-        end: &Node<N, E, Ty, Ix, D>,
+        start: &Node<N, EdgeKind, Ty, Ix, D>,
+        end: &Node<N, EdgeKind, Ty, Ix, D>,
         ctx: &DrawContext,
     ) -> Vec<egui::Shape> {
         let style = match self.selected {
             true => ctx.ctx.style().visuals.widgets.active,
             false => ctx.ctx.style().visuals.widgets.inactive,
         };
-        let color = style.fg_stroke.color;
+        // the look the glyph gives the edge, the same the exporters draw it with
+        let key = (start.id().index(), end.id().index(), self.order);
+        if let Some(look) = ctx.ctx.data(|data| {
+            data.get_temp::<Arc<EdgeLooks>>(egui::Id::new(EDGE_LOOKS))
+                .and_then(|looks| looks.get(&key).copied())
+        }) {
+            self.look = look;
+            self.width = look.width;
+        }
+        let color = match (self.look.color, self.selected) {
+            (Some(color), false) => color,
+            _ => style.fg_stroke.color,
+        };
 
-        if start.id() == end.id() {
+        let stroke_edge = Stroke::new(self.width * ctx.meta.zoom, color);
+        let marks = ctx
+            .ctx
+            .data(|data| data.get_temp::<EdgeMarks>(egui::Id::new(EDGE_MARKS)))
+            .unwrap_or_default();
+
+        // the drawn curve, its points and the last control point with the end the arrow sits on
+        let (curve, path, [control_point, edge_end]) = if start.id() == end.id() {
             // draw loop
             let node_size = node_size(start);
-            let stroke = Stroke::new(self.width * ctx.meta.zoom, color);
-            return vec![shape_looped(
+            let shape = shape_looped(
                 ctx.meta.canvas_to_screen_size(node_size),
                 ctx.meta.canvas_to_screen_pos(start.location()),
-                stroke,
+                stroke_edge,
                 self,
-            )
-            .into()];
-        }
-
-        let dir = (end.location() - start.location()).normalized();
-        let start_connector_point = start.display().closest_boundary_point(dir);
-        let end_connector_point = end.display().closest_boundary_point(-dir);
-
-        let edge_start = start_connector_point;
-        let edge_end = end_connector_point;
-
-        let stroke_edge = Stroke::new(self.width * ctx.meta.zoom, color);
+            );
+            let [_, _, control_point, edge_start] = shape.points;
+            let path = shape.flatten(None);
+            (Shape::from(shape), path, [control_point, edge_start])
+        } else {
+            // draw the edge in its style
+            let points = curve_points(start, end, self).map(|p| ctx.meta.canvas_to_screen_pos(p));
+
+            let line_curved = QuadraticBezierShape::from_points_stroke(
+                points,
+                false,
+                Color32::TRANSPARENT,
+                stroke_edge,
+            );
+            let path = line_curved.flatten(None);
+            (Shape::from(line_curved), path, [points[1], points[2]])
+        };
 
-        // draw curved edge
-
-        let dir_perpendicular = Vec2::new(-dir.y, dir.x);
-        let center_point = point_between(edge_start, edge_end);
-        let control_point =
-            center_point + dir_perpendicular * self.curve_size * (self.order + 1) as f32;
-
-        let line_curved = QuadraticBezierShape::from_points_stroke(
-            [
-                ctx.meta.canvas_to_screen_pos(edge_start),
-                ctx.meta.canvas_to_screen_pos(control_point),
-                ctx.meta.canvas_to_screen_pos(edge_end),
-            ],
-            false,
-            Color32::TRANSPARENT,
-            stroke_edge,
-        );
-
-        vec![line_curved.into()]
+        let mut shapes = match (self.between, marks.flow) {
+            (false, false) => vec![curve],
+            (between, flow) => {
+                let (dash, gap) = match between {
+                    true => (DASH_LENGTH, GAP_LENGTH),
+                    false => (FLOW_DASH_LENGTH, FLOW_GAP_LENGTH),
+                };
+                let offset = match flow {
+                    true => ctx.ctx.input(|input| input.time) as f32 * FLOW_SPEED,
+                    false => 0.0,
+                };
+                dashes(&path, dash, gap, offset)
+                    .into_iter()
+                    .map(|dash| Shape::line(dash, stroke_edge))
+                    .collect()
+            }
+        };
+        if marks.arrows {
+            shapes.push(arrowhead(
+                control_point,
+                edge_end,
+                self.tip_size * ctx.meta.zoom,
+                self.tip_angle,
+                color,
+            ));
+        }
+        shapes
     }
 
-    fn update(&mut self, state: &EdgeProps<E>) {
+    fn update(&mut self, state: &EdgeProps<EdgeKind>) {
         self.order = state.order;
         self.selected = state.selected;
     }
@@ -146,37 +274,66 @@ fn shape_looped(
     )
 }
 
-fn shape_curved(
-    pos_start: Pos2,
-    pos_end: Pos2,
-    size_start: f32,
-    size_end: f32,
-    stroke: Stroke,
-    e: &EdgeShape,
-) -> QuadraticBezierShape {
-    let vec = pos_end - pos_start;
-    let dist: f32 = vec.length();
-    let dir = vec / dist;
-
-    let start_node_radius_vec = Vec2::new(size_start, size_start) * dir;
-    let end_node_radius_vec = Vec2::new(size_end, size_end) * dir;
-
-    let tip_end = pos_start + vec - end_node_radius_vec;
-
-    let edge_start = pos_start + start_node_radius_vec;
-    let edge_end = pos_end + end_node_radius_vec;
+//Cuts a path into dashes, moved forward along it by offset
+fn dashes(path: &[Pos2], dash: f32, gap: f32, offset: f32) -> Vec<Vec<Pos2>> {
+    let period = dash + gap;
+    let mut dashes = vec![];
+    let mut current = vec![];
+    let mut walked = 0.0;
+    path.windows(2).for_each(|segment| {
+        let (from, to) = (segment[0], segment[1]);
+        let length = from.distance(to);
+        let mut along = 0.0;
+        while along < length {
+            let phase = (walked + along - offset).rem_euclid(period);
+            let drawing = phase < dash;
+            let left = if drawing {
+                dash - phase
+            } else {
+                period - phase
+            };
+            let next = (along + left.max(0.01)).min(length);
+            if drawing {
+                if current.is_empty() {
+                    current.push(from + (to - from) * (along / length));
+                }
+                current.push(from + (to - from) * (next / length));
+            } else if !current.is_empty() {
+                dashes.push(std::mem::take(&mut current));
+            }
+            along = next;
+        }
+        walked += length;
+    });
+    if current.len() > 1 {
+        dashes.push(current);
+    }
+    dashes
+}
 
-    let dir_perpendicular = Vec2::new(-dir.y, dir.x);
-    let center_point = (edge_start + tip_end.to_vec2()).to_vec2() / 2.0;
-    let control_point =
-        (center_point + dir_perpendicular * e.curve_size * (e.order + 1) as f32).to_pos2();
+//A filled arrowhead with its point at tip, coming from the direction of control
+fn arrowhead(control: Pos2, tip: Pos2, size: f32, angle: f32, color: Color32) -> Shape {
+    let back = (control - tip).normalized() * size;
+    let side = |angle: f32| tip + Rot2::from_angle(angle) * back;
+    Shape::convex_polygon(vec![tip, side(angle), side(-angle)], color, Stroke::NONE)
+}
 
-    QuadraticBezierShape::from_points_stroke(
-        [edge_start, control_point, edge_end],
-        false,
-        stroke.color,
-        stroke,
-    )
+//The curve of an edge between two different nodes on the canvas
+fn curve_points<N: Clone, Ty: EdgeType, Ix: IndexType, D: DisplayNode<N, EdgeKind, Ty, Ix>>(
+    start: &Node<N, EdgeKind, Ty, Ix, D>,
+    end: &Node<N, EdgeKind, Ty, Ix, D>,
+    e: &EdgeShape,
+) -> [Pos2; 3] {
+    let EdgeLook { style, ring, .. } = e.look;
+    style
+        .curve(
+            [to_point(start.location()), to_point(end.location())],
+            [node_size(start), node_size(end)],
+            e.curve_size,
+            e.order,
+            ring,
+        )
+        .map(to_pos2)
 }
 
 fn is_inside_loop<E: Clone, N: Clone, Ix: IndexType, Ty: EdgeType, D: DisplayNode<N, E, Ty, Ix>>(
@@ -190,31 +347,17 @@ fn is_inside_loop<E: Clone, N: Clone, Ix: IndexType, Ty: EdgeType, D: DisplayNod
     is_point_on_cubic_bezier_curve(pos, shape, e.width)
 }
 
-fn is_inside_curve<
-    N: Clone,
-    E: Clone,
-    Ty: EdgeType,
-    Ix: IndexType,
-    D: DisplayNode<N, E, Ty, Ix>,
->(
-    node_start: &Node<N, E, Ty, Ix, D>,
-    node_end: &Node<N, E, Ty, Ix, D>,
+fn is_inside_curve<N: Clone, Ty: EdgeType, Ix: IndexType, D: DisplayNode<N, EdgeKind, Ty, Ix>>(
+    node_start: &Node<N, EdgeKind, Ty, Ix, D>,
+    node_end: &Node<N, EdgeKind, Ty, Ix, D>,
     e: &EdgeShape,
     pos: Pos2,
 ) -> bool {
-    let pos_start = node_start.location();
-    let pos_end = node_end.location();
-
-    let size_start = node_size(node_start);
-    let size_end = node_size(node_end);
-
-    let shape = shape_curved(
-        pos_start,
-        pos_end,
-        size_start,
-        size_end,
+    let shape = QuadraticBezierShape::from_points_stroke(
+        curve_points(node_start, node_end, e),
+        false,
+        Color32::TRANSPARENT,
         Stroke::default(),
-        e,
     );
     is_point_on_quadratic_bezier_curve(pos, shape, e.width)
 }
@@ -290,11 +433,3 @@ fn is_point_on_bezier_curve(point: Pos2, curve_points: Vec<Pos2>, width: f32) ->
     }
     false
 }
-
-/// finds point exactly in the middle between 2 points
-fn point_between(p1: Pos2, p2: Pos2) -> Pos2 {
-    let base = p1 - p2;
-    let base_len = base.length();
-    let dir = base / base_len;
-    p1 - (base_len / 2.) * dir
-}
@@ -0,0 +1,110 @@
+use std::future::Future;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use eframe::egui;
+
+/// What a picked file is going to be used for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FileKind {
+    Rules,
+    Project,
+    Theme,
+}
+
+/// A file the user picked, read into memory.
+pub struct OpenedFile {
+    pub kind: FileKind,
+    pub name: String,
+    pub bytes: Vec<u8>,
+}
+
+/// Opens files through the native file dialog or the browser file picker.
+///
+/// Picking a file is asynchronous on the web, so picked files are handed back
+/// through [`FileDialogs::poll`] on a later frame on every platform.
+pub struct FileDialogs {
+    sender: Sender<OpenedFile>,
+    receiver: Receiver<OpenedFile>,
+}
+
+impl FileDialogs {
+    pub fn new() -> Self {
+        let (sender, receiver) = channel();
+        Self { sender, receiver }
+    }
+
+    pub fn open(&self, ctx: &egui::Context, kind: FileKind, filter: (&str, &[&str])) {
+        let dialog = rfd::AsyncFileDialog::new().add_filter(filter.0, filter.1);
+        let sender = self.sender.clone();
+        let ctx = ctx.clone();
+        execute(async move {
+            if let Some(file) = dialog.pick_file().await {
+                let opened = OpenedFile {
+                    kind,
+                    name: file.file_name(),
+                    bytes: file.read().await,
+                };
+                sender.send(opened).ok();
+                ctx.request_repaint();
+            }
+        });
+    }
+
+    //Returns the next file the user picked, if one has been read since the last frame
+    pub fn poll(&self) -> Option<OpenedFile> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn execute<F: Future<Output = ()> + Send + 'static>(f: F) {
+    std::thread::spawn(move || pollster::block_on(f));
+}
+
+#[cfg(target_arch = "wasm32")]
+fn execute<F: Future<Output = ()> + 'static>(f: F) {
+    wasm_bindgen_futures::spawn_local(f);
+}
+
+//Ask where to save a file and write it there
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save(name: &str, _mime: &str, bytes: &[u8]) -> Result<(), String> {
+    let extension = name.rsplit('.').next().unwrap_or_default();
+    if let Some(path) = rfd::FileDialog::new()
+        .set_file_name(name)
+        .add_filter(extension, &[extension])
+        .save_file()
+    {
+        std::fs::write(path, bytes).map_err(|error| error.to_string())?;
+    }
+    Ok(())
+}
+
+//Hand the file to the browser as a download
+#[cfg(target_arch = "wasm32")]
+pub fn save(name: &str, mime: &str, bytes: &[u8]) -> Result<(), String> {
+    use wasm_bindgen::JsCast;
+
+    let js_error = |error: wasm_bindgen::JsValue| format!("{error:?}");
+
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+    let mut options = web_sys::BlobPropertyBag::new();
+    options.type_(mime);
+    let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options)
+        .map_err(js_error)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob).map_err(js_error)?;
+
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or("the page has no document")?;
+    let anchor = document
+        .create_element("a")
+        .map_err(js_error)?
+        .dyn_into::<web_sys::HtmlAnchorElement>()
+        .map_err(|_| "could not create a download link")?;
+    anchor.set_href(&url);
+    anchor.set_download(name);
+    anchor.click();
+
+    web_sys::Url::revoke_object_url(&url).map_err(js_error)
+}
@@ -4,6 +4,13 @@ use eframe::{egui, App, CreationContext};
 use egui::Context;
 
 use egui_graphs::{Graph, GraphView, SettingsInteraction, SettingsNavigation};
+use lsegui_core::{
+    to_dot, to_dxf, to_gcode, to_graph_json, to_graphml, to_hpgl, to_obj, to_png, to_stencil_dxf,
+    to_stencil_svg, to_stl, to_svg, BaseShape, CircleLayout, Color, ColorScheme, ConnectionTable,
+    DxfOptions, EdgeKind, EdgeStyle, ExtrudeOptions, Glyph, GlyphStyle, History, LabelFont,
+    PlotOptions, Point, Project, ProjectError, RasterOptions, Scene, SharedGlyph, Theme,
+    DEFAULT_PALETTE,
+};
 use petgraph::{
     stable_graph::{DefaultIx, NodeIndex, StableGraph},
     Directed,
@@ -28,7 +35,12 @@ mod node;
 use node::NodeShape;
 
 mod circle_layout;
-use circle_layout::CircleLayout;
+//Opening files on native and web
+mod file_io;
+use file_io::{FileDialogs, FileKind, OpenedFile};
+//Guessing the phrase of a glyph
+mod puzzle;
+use puzzle::Puzzle;
 
 /* #[derive(Clone)]
 struct Circles {
@@ -301,551 +313,799 @@ impl Circle {
     }
 } */
 
-#[derive(Clone)]
-pub struct Word {
-    word: String,
-    nodes: Vec<NodeIndex<u32>>,
-    layout_top: NodeLayout,
-    layout_bottom: NodeLayout,
+pub struct Lsegui {
+    //The graph that will be displayed
+    pub g: Graph<NodeIndex, EdgeKind, Directed, DefaultIx, NodeShape, EdgeShape>,
+    //The user input string that will be used to create the graph
+    input_string: String,
+    //The phrase the glyph was last drawn from, behind the input while it can't be drawn
+    drawn_phrase: String,
+    //Boolean to display the graph once the user has entered a phrase
+    graph_show: bool,
+    //The processed phrase and the circles to display the nodes in the graph
+    glyph: Glyph,
+    //The letter connections used to build the graph
+    rules: ConnectionTable,
+    //Where the connection table was loaded from
+    rules_name: String,
+    //Problems found in the last connection table that failed to load
+    rules_errors: Vec<String>,
+    //File dialogs that are still waiting for the user
+    files: FileDialogs,
+    //Message about the last thing that went wrong
+    status: String,
+    //Size and background of exported PNGs
+    raster: RasterOptions,
+    //Paper and speeds for plotter exports
+    plot: PlotOptions,
+    //Size and cutting widths for DXF exports
+    cut: DxfOptions,
+    //Size, heights and base for 3D printed exports
+    solid: ExtrudeOptions,
+    //Guessing game with the letters hidden, while it's being played
+    puzzle: Option<Puzzle>,
+    //When the phrase was last typed in, while its preview is still to be drawn
+    edited_at: Option<f64>,
+    //Edits that can be undone and redone
+    history: History,
+    //The glyph as of the last edit, which the next edit is undone back to
+    last_state: Option<Project>,
+    //Dragged nodes slide along the ring of their word instead of going anywhere
+    on_ring: bool,
+    //How edges are drawn unless they were given a style of their own
+    edge_style: EdgeStyle,
+    //Arrowheads and flowing dashes that show which way the edges point
+    edge_marks: edge::EdgeMarks,
+    //What the colours of the glyph follow and the colours they pick from
+    color_scheme: ColorScheme,
+    palette: Vec<Color>,
+    //Colours and sizes of the canvas, and whether the theme editor is open
+    theme: Theme,
+    theme_open: bool,
 }
 
-impl Default for Word {
-    fn default() -> Self {
-        Self {
-            word: String::new(),
-            nodes: vec![],
-            layout_top: Alone,
-            layout_bottom: Alone,
+impl Lsegui {
+    fn new(cc: &CreationContext<'_>, shared: Option<SharedGlyph>) -> Self {
+        // Initialize the graph
+        let g = StableGraph::new();
+        //Apply the style from the theme module
+        let glyph_theme = Theme::dark();
+        cc.egui_ctx.set_style(theme::themed_style(&glyph_theme));
+        let glyph = Glyph::new("Default Phrase").expect("the default phrase should lay out");
+
+        let mut app = Self {
+            //By default the graph is empty and not displayed
+            g: Graph::from(&g),
+            input_string: String::new(),
+            drawn_phrase: String::new(),
+            graph_show: false,
+            glyph,
+            rules: ConnectionTable::default(),
+            rules_name: String::from("Default"),
+            rules_errors: vec![],
+            files: FileDialogs::new(),
+            status: String::new(),
+            raster: RasterOptions::default(),
+            plot: PlotOptions::default(),
+            cut: DxfOptions::default(),
+            solid: ExtrudeOptions::default(),
+            puzzle: None,
+            edited_at: None,
+            history: History::default(),
+            last_state: None,
+            on_ring: false,
+            edge_style: EdgeStyle::default(),
+            edge_marks: edge::EdgeMarks::default(),
+            color_scheme: ColorScheme::default(),
+            palette: DEFAULT_PALETTE.to_vec(),
+            theme: glyph_theme,
+            theme_open: false,
+        };
+        app.load_startup_rules();
+        if let Some(shared) = shared {
+            app.open_shared(shared);
         }
+        app
     }
-}
 
-impl Word {
-    fn new(word: String, nodes: Vec<NodeIndex<u32>>) -> Self {
-        Self {
-            word,
-            nodes,
-            layout_top: Alone,
-            layout_bottom: Alone,
+    //Use a rules.toml in the working directory instead of the bundled connection table if there is one
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_startup_rules(&mut self) {
+        if let Ok(source) = std::fs::read_to_string(STARTUP_RULES) {
+            self.set_rules(STARTUP_RULES, &source);
         }
     }
-}
 
-pub struct Phrase {
-    //phrase: Vec<String>,
-    phrase_words: Vec<Word>,
-    graph: StableGraph<(), ()>,
-}
+    #[cfg(target_arch = "wasm32")]
+    fn load_startup_rules(&mut self) {}
 
-impl Phrase {
-    fn new(phrase: &str) -> Self {
-        let mut g: StableGraph<(), ()> = StableGraph::new();
-
-        let phrase = phrase
-            .chars()
-            .filter(|c| c.is_ascii_alphabetic() || c.is_whitespace())
-            .collect::<String>()
-            .to_uppercase()
-            .split_whitespace()
-            .map(|word| {
-                if word.chars().next() == word.chars().last() {
-                    let new_word = word.chars().take(word.len() - 1).collect::<String>();
-                    new_word
-                } else {
-                    word.to_string()
+    fn set_rules(&mut self, name: &str, source: &str) {
+        match ConnectionTable::parse(source) {
+            Ok(rules) => {
+                self.rules = rules;
+                self.rules_name = name.to_string();
+                self.rules_errors.clear();
+                //Rebuild the graph so the new connections show up straight away
+                if self.graph_show {
+                    let phrase = self.input_string.clone();
+                    self.graph_creation(&phrase);
                 }
-            })
-            .collect::<Vec<_>>();
-        let mut phrase_words = vec![];
-        phrase.clone().iter().for_each(|word| {
-            let mut node_indices: Vec<NodeIndex<u32>> = vec![];
-            word.chars().for_each(|_char| {
-                node_indices.push(g.add_node(()));
+            }
+            Err(errors) => {
+                self.rules_errors = errors.iter().map(|error| error.to_string()).collect()
+            }
+        }
+    }
+
+    fn open_file(&mut self, file: OpenedFile) {
+        match file.kind {
+            FileKind::Rules => match String::from_utf8(file.bytes) {
+                Ok(source) => self.set_rules(&file.name, &source),
+                Err(_) => self.rules_errors = vec![format!("{} is not a text file", file.name)],
+            },
+            FileKind::Project => self.open_project(&file.name, file.bytes),
+            FileKind::Theme => {
+                self.status = match String::from_utf8(file.bytes)
+                    .map_err(|_| String::from("not a text file"))
+                    .and_then(|source| Theme::from_json(&source))
+                {
+                    Ok(theme) => {
+                        self.theme = theme;
+                        self.checkpoint();
+                        String::new()
+                    }
+                    Err(error) => format!("Could not open {}: {error}", file.name),
+                };
+            }
+        }
+    }
+
+    //Show the glyph from a share link
+    fn open_shared(&mut self, shared: SharedGlyph) {
+        match shared.glyph() {
+            Ok(glyph) => {
+                if let Some(rules) = shared.rules {
+                    self.rules = rules;
+                    self.rules_name = String::from("Shared link");
+                }
+                self.input_string = shared.phrase.clone();
+                self.drawn_phrase = shared.phrase;
+                self.glyph = glyph;
+                self.show_glyph();
+                self.checkpoint();
+            }
+            Err(error) => self.status = format!("Could not show the shared glyph: {error}"),
+        }
+    }
+
+    //Put a link to the glyph as it is now on the clipboard
+    fn copy_link(&mut self, ui: &egui::Ui) {
+        self.sync_node_positions();
+        let shared = SharedGlyph::new(&self.drawn_phrase, &self.glyph, &self.rules);
+        let link = format!("{}#{}", share_base_url(), shared.to_fragment());
+        ui.output_mut(|output| output.copied_text = link);
+    }
+
+    //Replace everything with a saved project, keeping the current glyph if it can't be read
+    fn open_project(&mut self, name: &str, bytes: Vec<u8>) {
+        let opened = String::from_utf8(bytes)
+            .map_err(|_| ProjectError::Format(String::from("not a text file")))
+            .and_then(|source| Project::from_json(&source))
+            .and_then(|mut project| {
+                let history = std::mem::take(&mut project.history);
+                self.restore(project)?;
+                self.history = history;
+                Ok(())
             });
+        if let Err(error) = opened {
+            self.status = format!("Could not open {name}: {error}");
+        }
+    }
+
+    //Show a project as it was saved or snapshotted, keeping the current glyph if it can't be rebuilt
+    fn restore(&mut self, project: Project) -> Result<(), ProjectError> {
+        let rules = project.connection_table()?;
+        self.glyph = project.glyph()?;
+        self.rules = rules;
+        self.rules_name = project.rules_name.clone();
+        self.input_string = project.phrase.clone();
+        self.drawn_phrase = project.phrase.clone();
+        self.edge_style = project.edge_style;
+        self.color_scheme = project.color_scheme;
+        self.palette = project.palette.clone();
+        //Custom themes saved by older versions only kept their name, so the current one is kept
+        if let Some(theme) = project.saved_theme() {
+            self.theme = theme;
+        }
+        self.edited_at = None;
+        self.show_glyph();
+        //Showing it already counts as the latest state, it isn't a new edit
+        self.last_state = Some(project);
+        Ok(())
+    }
+
+    //The glyph as it is shown now, as a project without its history
+    fn current_state(&mut self) -> Project {
+        self.sync_node_positions();
+        let mut project = Project::new(
+            &self.drawn_phrase,
+            &self.glyph,
+            &self.rules_name,
+            &self.rules,
+            &self.theme,
+        );
+        project.edge_style = self.edge_style;
+        project.color_scheme = self.color_scheme;
+        project.palette = self.palette.clone();
+        project
+    }
+
+    //The style the glyph is shown and exported with
+    fn glyph_style(&self) -> GlyphStyle {
+        //Colouring by letter would give the puzzle away
+        let color_scheme = match (self.color_scheme, &self.puzzle) {
+            (ColorScheme::Plain | ColorScheme::Word, _) | (_, None) => self.color_scheme,
+            (_, Some(_)) => ColorScheme::Plain,
+        };
+        GlyphStyle {
+            edge_style: self.edge_style,
+            color_scheme,
+            palette: self.palette.clone(),
+            ..self.theme.glyph_style()
+        }
+    }
 
-            let word = Word::new(word.to_string(), node_indices);
+    //Pick a built in theme or change the colours and sizes of the current one
+    fn theme_ui(&mut self, ctx: &Context, ui: &mut egui::Ui) {
+        let before = self.theme.clone();
+        ui.horizontal(|ui| {
+            Theme::NAMES.iter().for_each(|name| {
+                if ui
+                    .selectable_label(self.theme.name == *name, *name)
+                    .clicked()
+                {
+                    self.theme = Theme::named(name).expect("built in themes should exist");
+                }
+            });
+        });
+        ui.checkbox(&mut self.theme.dark, "Dark widgets");
+        let edited = &mut self.theme;
+        egui::Grid::new("theme").num_columns(2).show(ui, |ui| {
+            ui.label("Background");
+            theme::color_edit(ui, &mut edited.background);
+            ui.end_row();
+            ui.label("Rings");
+            ui.horizontal(|ui| {
+                theme::color_edit(ui, &mut edited.ring_color);
+                ui.add(
+                    egui::DragValue::new(&mut edited.ring_width)
+                        .clamp_range(0.5..=20.0)
+                        .speed(0.1),
+                );
+            });
+            ui.end_row();
+            ui.label("Letters");
+            ui.horizontal(|ui| {
+                theme::color_edit(ui, &mut edited.node_color);
+                ui.add(
+                    egui::DragValue::new(&mut edited.node_radius)
+                        .clamp_range(0.5..=20.0)
+                        .speed(0.1),
+                );
+            });
+            ui.end_row();
+            ui.label("Edges");
+            ui.horizontal(|ui| {
+                theme::color_edit(ui, &mut edited.edge_color);
+                theme::color_edit(ui, &mut edited.between_color)
+                    .on_hover_text("Edges between words");
+                ui.add(
+                    egui::DragValue::new(&mut edited.edge_width)
+                        .clamp_range(0.5..=20.0)
+                        .speed(0.1),
+                );
+            });
+            ui.end_row();
+            ui.label("Labels");
+            ui.horizontal(|ui| {
+                theme::color_edit(ui, &mut edited.label_color);
+                ui.add(
+                    egui::DragValue::new(&mut edited.label_size)
+                        .clamp_range(1.0..=20.0)
+                        .speed(0.1),
+                );
+                egui::ComboBox::from_id_source("label font")
+                    .selected_text(edited.label_font.name())
+                    .show_ui(ui, |ui| {
+                        LabelFont::ALL.into_iter().for_each(|font| {
+                            ui.selectable_value(&mut edited.label_font, font, font.name());
+                        });
+                    });
+            });
+            ui.end_row();
+        });
+        //Once edited it's no longer the built in theme it started from
+        if self.theme != before && self.theme.name == before.name {
+            if let Some(builtin) = Theme::named(&self.theme.name) {
+                if builtin != self.theme {
+                    self.theme.name = String::from("custom");
+                }
+            }
+        }
+        //Dragged sizes and colours are added to the history once the pointer is let go
+        if self.theme != before && !ui.input(|i| i.pointer.any_down()) {
+            self.checkpoint();
+        }
 
-            phrase_words.push(word);
+        ui.separator();
+        ui.horizontal(|ui| {
+            if ui.button("Load").clicked() {
+                self.files
+                    .open(ctx, FileKind::Theme, ("LSegui theme", &["json"]));
+            }
+            if ui.button("Save").clicked() {
+                let name = format!("{}.json", self.theme.name);
+                self.status =
+                    match file_io::save(&name, "application/json", self.theme.to_json().as_bytes())
+                    {
+                        Ok(()) => String::new(),
+                        Err(error) => format!("Could not save the theme: {error}"),
+                    };
+            }
         });
+    }
 
-        Self {
-            //phrase,
-            phrase_words,
-            graph: g,
+    //Pick what the colours of the glyph follow
+    fn color_scheme_ui(&mut self, ui: &mut egui::Ui) {
+        let before = self.color_scheme;
+        egui::ComboBox::from_label("Colours")
+            .selected_text(self.color_scheme.name())
+            .show_ui(ui, |ui| {
+                ColorScheme::ALL.into_iter().for_each(|scheme| {
+                    ui.selectable_value(&mut self.color_scheme, scheme, scheme.name());
+                });
+            });
+        if self.color_scheme != before {
+            self.checkpoint();
         }
     }
-    fn analyse_phrase(&mut self) {
-        //Analyse the phrase, given N words, where 1 is the first word and N is the last word
-        //for each word in the phrase:
-        //  find a letter shared between each subsequent word, so that word 1 shares a letter with word 2, word 2 shares a letter with word 3, etc.
-        //  if there is no letter shared between the current word and the next word
-        //      then shift the nodes of each of the two words away from the border between the two words
-        //      so that the nodes of the current word are shifted away from the bottom most point of the circle of the current word
-        //      and the nodes of the next word are shifted away from the top most point of the circle of the next word
-        //      node shift should shift the nodes as little distance as possible
-
-        //If the phrase has more than one word
-        /* if self.phrase_words.len() > 1 {
-            self.phrase_words
-                .clone()
-                .iter()
-                .enumerate()
-                .for_each(|(cword_index, word)| {
-                    //If the current word is not the last word in the phrase
-                    if self.phrase_words.len() > cword_index + 1 {
-                        let current_word = word.clone();
-                        let next_word = self.phrase_words[cword_index + 1].clone();
-                        /* let current_word_char_node_pairs = current_word
-                            .word
-                            .chars()
-                            .zip(current_word.nodes.clone())
-                            .collect::<Vec<_>>();
-                        let next_word_char_node_pairs = next_word
-                            .word
-                            .chars()
-                            .zip(next_word.nodes.clone())
-                            .collect::<Vec<_>>();
-                        let current_next_char_pairs = current_word
-                            .word
-                            .chars()
-                            .zip(next_word.word.chars().rev().clone())
-                            .collect::<Vec<_>>(); */
-                        let mut shared_char: char = ' ';
-                        current_word.word.chars().enumerate().for_each(|(ci, c)| {
-                            //If the last character of the current word is the same as the first character of the next word
-                            if ci == current_word.word.len() - 1 && next_word.word.starts_with(c) {
-                                shared_char = c;
-                                self.phrase_words[cword_index].layout_bottom = SameCharBottom;
-                                self.phrase_words[cword_index + 1].layout_top = SameCharTop;
-                            }
-                            //If the last character of the current word is not the same as the first character of the next word
-                            if ci == current_word.word.len() - 1 && !next_word.word.starts_with(c) {
-                                self.phrase_words[cword_index].layout_bottom = RepelBottom;
-                                self.phrase_words[cword_index + 1].layout_top = RepelTop;
-                            }
-                            //If the current word starts with a character in the previous word
-                            if ci == 0 && next_word.word.ends_with(c) {
-                                shared_char = c;
-                                self.phrase_words[cword_index].layout_bottom = SameCharBottom;
-                                self.phrase_words[cword_index + 1].layout_top = SameCharTop;
-                            }
+
+    //What every colour stands for, with the palette they are picked from.
+    //Palette edits are added to the history once the pointer is let go.
+    fn legend_ui(&mut self, ui: &mut egui::Ui) {
+        let glyph_style = self.glyph_style();
+        ui.heading("Legend");
+        egui::ScrollArea::vertical()
+            .max_height(ui.available_height() / 2.0)
+            .show(ui, |ui| {
+                glyph_style
+                    .legend(&self.glyph)
+                    .into_iter()
+                    .for_each(|(name, color)| {
+                        ui.horizontal(|ui| {
+                            let (swatch, _) = ui.allocate_exact_size(
+                                egui::Vec2::splat(ui.spacing().interact_size.y * 0.6),
+                                egui::Sense::hover(),
+                            );
+                            ui.painter().rect_filled(swatch, 2.0, theme::color32(color));
+                            ui.label(name);
                         });
-                    }
-                })
-        } else {
-            //If the phrase only has one word
-            self.phrase_words[0].layout_top = Alone;
-            self.phrase_words[0].layout_bottom = Alone;
-        } */
+                    });
+            });
+        if glyph_style.color_scheme != self.color_scheme {
+            ui.label("Letter colours are hidden during the puzzle");
+        }
+
+        ui.separator();
+        ui.heading("Palette");
+        let mut removed = None;
+        self.palette.iter_mut().enumerate().for_each(|(i, color)| {
+            ui.horizontal(|ui| {
+                theme::color_edit(ui, color);
+                ui.label(color.to_hex());
+                if ui.small_button("Remove").clicked() {
+                    removed = Some(i);
+                }
+            });
+        });
+        //Schemes fall back to the plain colours without a palette, so keep at least one
+        if let Some(i) = removed.filter(|_| self.palette.len() > 1) {
+            self.palette.remove(i);
+            self.checkpoint();
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Add").clicked() {
+                let next = DEFAULT_PALETTE[self.palette.len() % DEFAULT_PALETTE.len()];
+                self.palette.push(next);
+                self.checkpoint();
+            }
+            if ui.button("Reset").clicked() {
+                self.palette = DEFAULT_PALETTE.to_vec();
+                self.checkpoint();
+            }
+        });
     }
-}
 
-#[derive(Clone, Debug)]
-enum NodeLayout {
-    /* RepelTop,
-    RepelBottom,
-    SameCharTop,
-    SameCharBottom, */
-    Alone,
-}
+    //Pick how edges are drawn, for all of them or just the selected ones
+    fn edge_style_ui(&mut self, ui: &mut egui::Ui) {
+        let before = self.edge_style;
+        egui::ComboBox::from_label("Edges")
+            .selected_text(self.edge_style.name())
+            .show_ui(ui, |ui| {
+                EdgeStyle::ALL.into_iter().for_each(|style| {
+                    ui.selectable_value(&mut self.edge_style, style, style.name());
+                });
+            });
 
-use NodeLayout::*;
+        let selected = self.g.selected_edges().to_vec();
+        let mut picked = None;
+        ui.add_enabled_ui(!selected.is_empty(), |ui| {
+            ui.menu_button("Selected edges", |ui| {
+                if ui.button("Like the rest").clicked() {
+                    picked = Some(None);
+                }
+                EdgeStyle::ALL.into_iter().for_each(|style| {
+                    if ui.button(style.name()).clicked() {
+                        picked = Some(Some(style));
+                    }
+                });
+                if picked.is_some() {
+                    ui.close_menu();
+                }
+            });
+        });
+        if let Some(style) = picked {
+            selected
+                .into_iter()
+                .for_each(|edge| self.glyph.set_edge_style(edge, style));
+        }
+        if picked.is_some() || self.edge_style != before {
+            self.checkpoint();
+        }
 
-pub struct Lsegui {
-    //The graph that will be displayed
-    pub g: Graph<(), (), Directed, DefaultIx, NodeShape, EdgeShape>,
-    //The user input string that will be used to create the graph
-    input_string: String,
-    //Boolean to display the graph once the user has entered a phrase
-    graph_show: bool,
-    //Circles to display the nodes in the graph
-    circles: CircleLayout,
-    //The processed phrase that the user entered
-    phrase: Phrase,
-}
+        ui.checkbox(&mut self.edge_marks.arrows, "Arrows")
+            .on_hover_text(
+                "Point every edge from the letter it starts at to the letter it connects to",
+            );
+        ui.checkbox(&mut self.edge_marks.flow, "Flow")
+            .on_hover_text("Let dashes run along every edge in the direction it connects");
+    }
 
-impl Lsegui {
-    fn new(cc: &CreationContext<'_>) -> Self {
-        // Initialize the graph
-        let g = StableGraph::new();
-        //Apply the style from the theme module
-        let style = theme::style();
-        cc.egui_ctx.set_style(style);
-        let circles = CircleLayout::new();
-        let phrase = Phrase::new("Default Phrase");
+    //Add the last state to the history if the glyph was edited since
+    fn checkpoint(&mut self) {
+        if !self.graph_show {
+            return;
+        }
+        let current = self.current_state();
+        match self.last_state.replace(current.clone()) {
+            Some(last) if last != current => self.history.record(last),
+            _ => (),
+        }
+    }
 
-        Self {
-            //By default the graph is empty and not displayed
-            g: Graph::from(&g),
-            input_string: String::new(),
-            graph_show: false,
-            circles,
-            phrase,
+    fn undo(&mut self) {
+        self.checkpoint();
+        let Some(current) = self.last_state.clone() else {
+            return;
+        };
+        if let Some(previous) = self.history.undo(current) {
+            if let Err(error) = self.restore(previous) {
+                self.status = format!("Could not undo: {error}");
+            }
         }
     }
+
+    fn redo(&mut self) {
+        self.checkpoint();
+        let Some(current) = self.last_state.clone() else {
+            return;
+        };
+        if let Some(next) = self.history.redo(current) {
+            if let Err(error) = self.restore(next) {
+                self.status = format!("Could not redo: {error}");
+            }
+        }
+    }
+
+    fn save_project(&mut self) {
+        let mut project = self.current_state();
+        project.history = self.history.clone();
+        self.status = match file_io::save(
+            "glyph.lsegui",
+            "application/json",
+            project.to_json().as_bytes(),
+        ) {
+            Ok(()) => String::new(),
+            Err(error) => format!("Could not save the project: {error}"),
+        };
+    }
     //Reset the graph and its metadata to ensure that the graph is ready for the next input
     fn reset_graph(&mut self, ui: &mut egui::Ui) {
+        self.graph_show = false;
         let g = StableGraph::new();
 
         self.g = Graph::from(&g);
 
         GraphView::<(), (), Directed, DefaultIx>::reset_metadata(ui);
-        self.circles = CircleLayout::new();
+        self.glyph.circles = CircleLayout::new();
     }
 
-    fn graph_creation(&mut self, phrase: &str) {
-        self.phrase = Phrase::new(phrase);
-
-        self.phrase.analyse_phrase();
-
-        self.phrase.phrase_words.iter().for_each(|word| {
-            let word_char_pairs = word.word.chars().zip(word.nodes.clone());
-
-            word_char_pairs
-                .clone()
-                .for_each(|(current_char, current_node)| {
-                    match current_char {
-                        'A' | 'a' => (),
-                        'B' | 'b' => {
-                            let connections = "A";
-                            refactor_connections_check(
-                                connections,
-                                &word_char_pairs,
-                                &mut self.phrase.graph,
-                                &current_node,
-                            );
-                        }
-                        'C' | 'c' => {
-                            let connections = "AB";
-                            refactor_connections_check(
-                                connections,
-                                &word_char_pairs,
-                                &mut self.phrase.graph,
-                                &current_node,
-                            );
-                        }
-                        'D' | 'd' => {
-                            let connections = "ABC";
-                            refactor_connections_check(
-                                connections,
-                                &word_char_pairs,
-                                &mut self.phrase.graph,
-                                &current_node,
-                            );
-                        }
-                        'E' | 'e' => {
-                            let connections = "ACD";
-                            refactor_connections_check(
-                                connections,
-                                &word_char_pairs,
-                                &mut self.phrase.graph,
-                                &current_node,
-                            );
-                        }
-                        'F' | 'f' => {
-                            let connections = "ABDE";
-                            refactor_connections_check(
-                                connections,
-                                &word_char_pairs,
-                                &mut self.phrase.graph,
-                                &current_node,
-                            );
-                        }
-                        'G' | 'g' => {
-                            let connections = "AEF";
-                            refactor_connections_check(
-                                connections,
-                                &word_char_pairs,
-                                &mut self.phrase.graph,
-                                &current_node,
-                            );
-                        }
-                        'H' | 'h' => {
-                            let connections = "ABEFG";
-                            refactor_connections_check(
-                                connections,
-                                &word_char_pairs,
-                                &mut self.phrase.graph,
-                                &current_node,
-                            );
-                        }
-                        'I' | 'i' => {
-                            let connections = "ABCEGH";
-                            refactor_connections_check(
-                                connections,
-                                &word_char_pairs,
-                                &mut self.phrase.graph,
-                                &current_node,
-                            );
-                        }
-                        'J' | 'j' => {
-                            let connections = "ACDEFGHI";
-                            refactor_connections_check(
-                                connections,
-                                &word_char_pairs,
-                                &mut self.phrase.graph,
-                                &current_node,
-                            );
-                        }
-                        'K' | 'k' => {
-                            let connections = "ABCIJ";
-                            refactor_connections_check(
-                                connections,
-                                &word_char_pairs,
-                                &mut self.phrase.graph,
-                                &current_node,
-                            );
-                        }
-                        'L' | 'l' => {
-                            let connections = "ACDIJK";
-                            refactor_connections_check(
-                                connections,
-                                &word_char_pairs,
-                                &mut self.phrase.graph,
-                                &current_node,
-                            );
-                        }
-                        'M' | 'm' => {
-                            let connections = "ABCDEIKL";
-                            refactor_connections_check(
-                                connections,
-                                &word_char_pairs,
-                                &mut self.phrase.graph,
-                                &current_node,
-                            );
-                        }
-                        'N' | 'n' => {
-                            let connections = "ACDEFHJKM";
-                            refactor_connections_check(
-                                connections,
-                                &word_char_pairs,
-                                &mut self.phrase.graph,
-                                &current_node,
-                            );
-                        }
-                        'O' | 'o' => {
-                            let connections = "ABEFGILMN";
-                            refactor_connections_check(
-                                connections,
-                                &word_char_pairs,
-                                &mut self.phrase.graph,
-                                &current_node,
-                            );
-                        }
-                        'P' | 'p' => {
-                            let connections = "ACGHIKLNO";
-                            refactor_connections_check(
-                                connections,
-                                &word_char_pairs,
-                                &mut self.phrase.graph,
-                                &current_node,
-                            );
-                        }
-                        'Q' | 'q' => {
-                            let connections = "ABCDEHIJKLMP";
-                            refactor_connections_check(
-                                connections,
-                                &word_char_pairs,
-                                &mut self.phrase.graph,
-                                &current_node,
-                            );
-                        }
-                        'R' | 'r' => {
-                            let connections = "ABCDEGHIKLOPQ";
-                            refactor_connections_check(
-                                connections,
-                                &word_char_pairs,
-                                &mut self.phrase.graph,
-                                &current_node,
-                            );
-                        }
-                        'S' | 's' => {
-                            let connections = "ADEFGHILMO";
-                            refactor_connections_check(
-                                connections,
-                                &word_char_pairs,
-                                &mut self.phrase.graph,
-                                &current_node,
-                            );
-                        }
-                        'T' | 't' => {
-                            let connections = "ACDEFHIJLMNOQS";
-                            refactor_connections_check(
-                                connections,
-                                &word_char_pairs,
-                                &mut self.phrase.graph,
-                                &current_node,
-                            );
-                        }
-                        'U' | 'u' => {
-                            let connections = "ACDFGIJKMPQRST";
-                            refactor_connections_check(
-                                connections,
-                                &word_char_pairs,
-                                &mut self.phrase.graph,
-                                &current_node,
-                            );
-                        }
-                        'V' | 'v' => {
-                            let connections = "ABDEFHJKLNPQS";
-                            refactor_connections_check(
-                                connections,
-                                &word_char_pairs,
-                                &mut self.phrase.graph,
-                                &current_node,
-                            );
-                        }
-                        'W' | 'w' => {
-                            let connections = "AV";
-                            refactor_connections_check(
-                                connections,
-                                &word_char_pairs,
-                                &mut self.phrase.graph,
-                                &current_node,
-                            );
-                        }
-                        'X' | 'x' => {
-                            let connections = "AW";
-                            refactor_connections_check(
-                                connections,
-                                &word_char_pairs,
-                                &mut self.phrase.graph,
-                                &current_node,
-                            );
-                        }
-                        'Y' | 'y' => {
-                            let connections = "AX";
-                            refactor_connections_check(
-                                connections,
-                                &word_char_pairs,
-                                &mut self.phrase.graph,
-                                &current_node,
-                            );
-                        }
-                        'Z' | 'z' => {
-                            let connections = "AY";
-                            refactor_connections_check(
-                                connections,
-                                &word_char_pairs,
-                                &mut self.phrase.graph,
-                                &current_node,
-                            );
-                        }
-                        _ => (),
-                    };
-                });
+    //Bring the glyph up to date with the nodes dragged around in the graph view
+    fn sync_node_positions(&mut self) {
+        let nodes = self
+            .glyph
+            .nodes()
+            .map(|(node, _, _)| node)
+            .collect::<Vec<_>>();
+        nodes.into_iter().for_each(|node| {
+            if let Some(shown) = self.g.node(node) {
+                let pos = to_point(shown.location());
+                if self.on_ring {
+                    self.glyph.circles.slide_node(node, pos);
+                } else {
+                    self.glyph.circles.set_node_pos(node, pos);
+                }
+            }
+        });
+    }
 
-            word.nodes.iter().for_each(|current_node| {
-                if self
-                    .phrase
-                    .graph
-                    .neighbors_directed(*current_node, petgraph::Direction::Outgoing)
-                    .count()
-                    == 0
-                    && self
-                        .phrase
-                        .graph
-                        .neighbors_directed(*current_node, petgraph::Direction::Incoming)
-                        .count()
-                        == 0
-                {
-                    //Out of the nodes in the current word,
-                    //connect the current node to the node
-                    //representing the character closest to the current character on the alphabet
-                    let mut closest_index = 0;
-                    let mut closest_distance = 26;
-                    let current_char = word_char_pairs
-                        .clone()
-                        .filter(|(_, n)| *n == *current_node)
-                        .map(|(c, _)| c)
-                        .next()
-                        .unwrap();
-                    word_char_pairs
-                        .clone()
-                        .filter(|(c, _)| *c != current_char)
-                        .enumerate()
-                        .for_each(|(i, (c, _))| {
-                            let distance = (current_char as i32 - c as i32).abs();
-                            if distance < closest_distance {
-                                closest_distance = distance;
-                                closest_index = i;
-                            }
-                        });
-                    self.phrase
-                        .graph
-                        .add_edge(*current_node, word.nodes[closest_index], ());
+    //Put nodes dragged off their ring back onto it
+    fn keep_nodes_on_ring(&mut self) {
+        self.sync_node_positions();
+        self.glyph.nodes().for_each(|(node, _, pos)| {
+            if let Some(shown) = self.g.node_mut(node) {
+                shown.set_location(to_pos2(pos));
+            }
+        });
+    }
+
+    //Move a word circle with its nodes as one piece, edges follow the nodes
+    fn place_circle(&mut self, i: usize, center: Point, rotation: f32) {
+        self.sync_node_positions();
+        let Some(circle) = self.glyph.circles.circle_list.get_mut(i) else {
+            return;
+        };
+        circle.set_placement(center, rotation);
+        circle
+            .node_pos_list
+            .node_pos_list
+            .iter()
+            .for_each(|node_pos| {
+                if let Some(node) = self.g.node_mut(node_pos.node) {
+                    node.set_location(to_pos2(node_pos.pos));
                 }
             });
-        });
+    }
 
-        self.g = Graph::from(&self.phrase.graph);
+    fn export_svg(&mut self) {
+        self.sync_node_positions();
+        let scene = Scene::new(&self.glyph, &self.glyph_style());
+        let svg = to_svg(&scene);
+        self.status = match file_io::save("glyph.svg", "image/svg+xml", svg.as_bytes()) {
+            Ok(()) => String::new(),
+            Err(error) => format!("Could not export the SVG: {error}"),
+        };
+    }
+
+    fn export_png(&mut self) {
+        self.sync_node_positions();
+        let scene = Scene::new(&self.glyph, &self.glyph_style());
+        self.status = match to_png(&scene, &self.raster)
+            .and_then(|png| file_io::save("glyph.png", "image/png", &png))
+        {
+            Ok(()) => String::new(),
+            Err(error) => format!("Could not export the PNG: {error}"),
+        };
+    }
 
-        self.phrase.phrase_words.iter().for_each(|word| {
-            for (node, letter) in word.nodes.iter().zip(word.word.chars()) {
-                self.g
-                    .node_mut(*node)
-                    .unwrap()
-                    .set_label(letter.to_string());
+    //G-code and HPGL are both drawn on the same plotter paper
+    fn export_plot(
+        &mut self,
+        file_name: &str,
+        write: fn(&Scene, &PlotOptions) -> Result<String, String>,
+    ) {
+        self.sync_node_positions();
+        let scene = Scene::new(&self.glyph, &self.glyph_style());
+        self.status = match write(&scene, &self.plot)
+            .and_then(|plot| file_io::save(file_name, "text/plain", plot.as_bytes()))
+        {
+            Ok(()) => String::new(),
+            Err(error) => format!("Could not export {file_name}: {error}"),
+        };
+    }
+
+    //The connection graph with the letters where they are laid out, for other tools to read
+    fn export_graph(&mut self, file_name: &str, mime: &str, write: fn(&Glyph) -> String) {
+        self.sync_node_positions();
+        self.status = match file_io::save(file_name, mime, write(&self.glyph).as_bytes()) {
+            Ok(()) => String::new(),
+            Err(error) => format!("Could not export {file_name}: {error}"),
+        };
+    }
+
+    fn export_dxf(&mut self) {
+        self.sync_node_positions();
+        let scene = Scene::new(&self.glyph, &self.glyph_style());
+        self.status = match to_dxf(&scene, &self.cut)
+            .and_then(|dxf| file_io::save("glyph.dxf", "image/vnd.dxf", dxf.as_bytes()))
+        {
+            Ok(()) => String::new(),
+            Err(error) => format!("Could not export the DXF: {error}"),
+        };
+    }
+
+    //Stencils are cut with the same widths as DXF exports, with bridges left across the slots
+    fn export_stencil(
+        &mut self,
+        file_name: &str,
+        mime: &str,
+        write: fn(&Scene, &DxfOptions) -> Result<String, String>,
+    ) {
+        self.sync_node_positions();
+        let scene = Scene::new(&self.glyph, &self.glyph_style());
+        self.status = match write(&scene, &self.cut)
+            .and_then(|stencil| file_io::save(file_name, mime, stencil.as_bytes()))
+        {
+            Ok(()) => String::new(),
+            Err(error) => format!("Could not export the stencil: {error}"),
+        };
+    }
+
+    //STL and OBJ are both extruded the same way
+    fn export_solid(
+        &mut self,
+        file_name: &str,
+        mime: &str,
+        write: fn(&Scene, &ExtrudeOptions) -> Result<Vec<u8>, String>,
+    ) {
+        self.sync_node_positions();
+        let scene = Scene::new(&self.glyph, &self.glyph_style());
+        self.status = match write(&scene, &self.solid)
+            .and_then(|solid| file_io::save(file_name, mime, &solid))
+        {
+            Ok(()) => String::new(),
+            Err(error) => format!("Could not export {file_name}: {error}"),
+        };
+    }
+
+    fn graph_creation(&mut self, phrase: &str) {
+        self.glyph = match Glyph::with_rules(phrase, &self.rules) {
+            Ok(glyph) => glyph,
+            Err(error) => {
+                self.status = format!("Could not draw the glyph: {error}");
+                self.graph_show = false;
+                return;
             }
-        });
+        };
+        self.drawn_phrase = phrase.to_string();
+        self.show_glyph();
+        self.checkpoint();
+    }
+
+    //Redraw the glyph for the phrase as typed, only rebuilding the words that changed
+    //so nodes dragged on the other words stay where they were put
+    fn preview_phrase(&mut self) {
+        let phrase = self.input_string.clone();
+        if !self.graph_show {
+            self.graph_creation(&phrase);
+            return;
+        }
+        self.sync_node_positions();
+        match self.glyph.update(&phrase, &self.rules) {
+            Ok(()) => {
+                self.drawn_phrase = phrase;
+                self.show_glyph();
+                self.checkpoint();
+            }
+            //Keep the last glyph that could be drawn up while the phrase is still being typed
+            Err(error) => self.status = format!("Could not draw the glyph: {error}"),
+        }
+    }
 
-        //self.node_circle_create();
-        self.circles.layout(&self.phrase, &mut self.g);
+    //Display the glyph's nodes where it laid them out
+    fn show_glyph(&mut self) {
+        self.status.clear();
+        self.graph_show = true;
+
+        //Every node carries its index in the glyph, which its colour is looked up by
+        self.g = Graph::from(&self.glyph.graph().map(|node, _| node, |_, kind| *kind));
+
+        self.glyph.nodes().for_each(|(node, _, pos)| {
+            self.g.node_mut(node).unwrap().set_location(to_pos2(pos));
+        });
+        self.show_labels();
     }
 
-    /* fn node_circle_create(&mut self) {
-        self.circles.circles.clear();
-        self.phrase.phrase_words.iter().for_each(|word| {
-            let circle = Circle::new(word.clone());
-            self.circles.circles.push(circle);
+    //Label every node with its letter, unless they are hidden for the puzzle
+    fn show_labels(&mut self) {
+        let hidden = self.puzzle.is_some();
+        self.glyph.nodes().for_each(|(node, letter, _)| {
+            if let Some(node) = self.g.node_mut(node) {
+                node.set_label(if hidden {
+                    String::new()
+                } else {
+                    letter.to_string()
+                });
+            }
         });
-        self.circles.circle_layout(&mut self.g);
-    } */
+    }
 }
 
-//Check if the char in the phrase is connected to any other char in the phrase and add an edge between them
-//Some nodes may not get any connections if the current word does not have any letters for the current character to connect to
-fn refactor_connections_check(
-    connections: &str,
-    word_char_pairs: &std::iter::Zip<std::str::Chars<'_>, std::vec::IntoIter<NodeIndex>>,
-    g: &mut StableGraph<(), ()>,
-    current_node: &NodeIndex,
-) {
-    connections.chars().for_each(|target_char| {
-        word_char_pairs
-            .clone()
-            .filter(|(c_c, _)| *c_c == target_char)
-            .for_each(|target_index| {
-                g.add_edge(*current_node, target_index.1, ());
-            });
-    });
+//Where the web build is deployed, for links copied from the native app
+#[cfg(not(target_arch = "wasm32"))]
+fn share_base_url() -> String {
+    String::from("https://atlas16a.github.io/LSegui/")
+}
+
+//The page the app is running on, without any fragment
+#[cfg(target_arch = "wasm32")]
+fn share_base_url() -> String {
+    web_sys::window()
+        .and_then(|window| {
+            let location = window.location();
+            Some(location.origin().ok()? + &location.pathname().ok()?)
+        })
+        .unwrap_or_default()
+}
+
+//Connection table picked up from the working directory at startup
+#[cfg(not(target_arch = "wasm32"))]
+const STARTUP_RULES: &str = "rules.toml";
+
+//Seconds of no typing before the glyph is redrawn for the new phrase
+const PREVIEW_DELAY: f64 = 0.3;
+
+//Convert a position on the glyph canvas to an egui position
+fn to_pos2(pos: Point) -> egui::Pos2 {
+    egui::Pos2::new(pos.x, pos.y)
+}
+
+//Convert an egui position to a position on the glyph canvas
+fn to_point(pos: egui::Pos2) -> Point {
+    Point::new(pos.x, pos.y)
 }
 
 impl App for Lsegui {
     fn update(&mut self, ctx: &Context, _: &mut eframe::Frame) {
+        while let Some(file) = self.files.poll() {
+            self.open_file(file);
+        }
+
+        //Text fields have their own undo while they are being typed in
+        if ctx.memory(|memory| memory.focus().is_none()) {
+            let redo = egui::Modifiers {
+                shift: true,
+                ..egui::Modifiers::COMMAND
+            };
+            if ctx.input_mut(|i| i.consume_key(redo, egui::Key::Z)) {
+                self.redo();
+            } else if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::Z)) {
+                self.undo();
+            }
+        }
+
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
                 ui.label("Enter a Phrase:");
                 //Take in user text input
-                let re = ui.text_edit_singleline(&mut self.input_string);
-                if re.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
-                    self.graph_show = false;
-                    println!("Input: {}", self.input_string);
-                    //Clear the current graph just in case
-                    self.reset_graph(ui);
-                    //Convert the input to a string slice
-                    let phrase = self.input_string.clone();
-                    let phrase = phrase.as_str();
-                    //Create the graph from the input
-                    self.graph_creation(phrase);
-                    //Display the graph
-                    self.graph_show = true;
-
-                    self.phrase.phrase_words.iter().for_each(|word| {
-                        println!("Word: {}", word.word);
-                        println!("Word layout_top{:?}", word.layout_top);
-                        println!("Word layout_bottom{:?}", word.layout_bottom);
-                    });
+                //Keep the answer out of sight while the puzzle is played
+                let re = ui.add(
+                    egui::TextEdit::singleline(&mut self.input_string)
+                        .password(self.puzzle.is_some()),
+                );
+                let now = ui.input(|i| i.time);
+                if re.changed() {
+                    self.edited_at = Some(now);
+                    ctx.request_repaint_after(std::time::Duration::from_secs_f64(PREVIEW_DELAY));
+                }
+                //Enter draws the phrase straight away instead of waiting for the typing to stop
+                let entered = re.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                let settled = self
+                    .edited_at
+                    .is_some_and(|edited_at| now - edited_at >= PREVIEW_DELAY);
+                if entered || settled {
+                    self.edited_at = None;
+                    self.preview_phrase();
                 }
             });
             ui.separator();
@@ -854,11 +1114,323 @@ impl App for Lsegui {
                 if ui.button("Reset").clicked() {
                     self.reset_graph(ui);
                 }
+                if ui
+                    .add_enabled(self.history.can_undo(), egui::Button::new("Undo"))
+                    .on_hover_text("Ctrl+Z")
+                    .clicked()
+                {
+                    self.undo();
+                }
+                if ui
+                    .add_enabled(self.history.can_redo(), egui::Button::new("Redo"))
+                    .on_hover_text("Ctrl+Shift+Z")
+                    .clicked()
+                {
+                    self.redo();
+                }
+                ui.separator();
+                if ui.button("Open").clicked() {
+                    self.files
+                        .open(ctx, FileKind::Project, ("LSegui project", &["lsegui"]));
+                }
+                ui.add_enabled_ui(self.graph_show, |ui| {
+                    if ui.button("Save").clicked() {
+                        self.save_project();
+                    }
+                    if ui.button("Copy link").clicked() {
+                        self.copy_link(ui);
+                    }
+                    if ui
+                        .checkbox(&mut self.on_ring, "Letters on ring")
+                        .on_hover_text("Dragged letters slide along their word circle")
+                        .changed()
+                        && self.on_ring
+                    {
+                        self.keep_nodes_on_ring();
+                        self.checkpoint();
+                    }
+                    let mut playing = self.puzzle.is_some();
+                    if ui.checkbox(&mut playing, "Puzzle").changed() {
+                        self.puzzle = playing.then(Puzzle::default);
+                        self.show_labels();
+                    }
+                });
+                ui.separator();
+                ui.add_enabled_ui(self.graph_show, |ui| {
+                    self.edge_style_ui(ui);
+                    self.color_scheme_ui(ui);
+                });
+                ui.toggle_value(&mut self.theme_open, "Theme");
+                ui.separator();
+                if ui.button("Load rules").clicked() {
+                    self.files
+                        .open(ctx, FileKind::Rules, ("Connection table", &["toml"]));
+                }
+                ui.label(format!("Rules: {}", self.rules_name));
+                let mut reach = self.rules.reach();
+                ui.label("Reach");
+                if ui
+                    .add(egui::DragValue::new(&mut reach).clamp_range(0..=8))
+                    .on_hover_text("How many neighbouring words letters also connect into")
+                    .changed()
+                {
+                    self.rules.set_reach(reach);
+                    //Only the edges between words change, so dragged nodes can stay
+                    self.preview_phrase();
+                }
+                ui.separator();
+                ui.add_enabled_ui(self.graph_show, |ui| {
+                    ui.menu_button("Export", |ui| {
+                        if ui.button("SVG").clicked() {
+                            self.export_svg();
+                            ui.close_menu();
+                        }
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            ui.label("PNG size");
+                            ui.add(
+                                egui::DragValue::new(&mut self.raster.width)
+                                    .clamp_range(16..=8192)
+                                    .suffix(" px"),
+                            );
+                            ui.label("x");
+                            ui.add(
+                                egui::DragValue::new(&mut self.raster.height)
+                                    .clamp_range(16..=8192)
+                                    .suffix(" px"),
+                            );
+                        });
+                        ui.checkbox(&mut self.raster.transparent, "Transparent background");
+                        if ui.button("PNG").clicked() {
+                            self.export_png();
+                            ui.close_menu();
+                        }
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            ui.label("Paper");
+                            egui::ComboBox::from_id_source("paper")
+                                .selected_text("Size")
+                                .show_ui(ui, |ui| {
+                                    PlotOptions::PAPERS
+                                        .iter()
+                                        .for_each(|(name, width, height)| {
+                                            if ui.button(*name).clicked() {
+                                                self.plot.paper_width = *width;
+                                                self.plot.paper_height = *height;
+                                            }
+                                        });
+                                });
+                            ui.add(
+                                egui::DragValue::new(&mut self.plot.paper_width)
+                                    .clamp_range(10.0..=2000.0)
+                                    .suffix(" mm"),
+                            );
+                            ui.label("x");
+                            ui.add(
+                                egui::DragValue::new(&mut self.plot.paper_height)
+                                    .clamp_range(10.0..=2000.0)
+                                    .suffix(" mm"),
+                            );
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Margin");
+                            ui.add(
+                                egui::DragValue::new(&mut self.plot.margin)
+                                    .clamp_range(0.0..=200.0)
+                                    .suffix(" mm"),
+                            );
+                            ui.label("Feed");
+                            ui.add(
+                                egui::DragValue::new(&mut self.plot.feed_rate)
+                                    .clamp_range(10.0..=20000.0)
+                                    .suffix(" mm/min"),
+                            );
+                        });
+                        ui.horizontal(|ui| {
+                            if ui.button("G-code").clicked() {
+                                self.export_plot("glyph.gcode", to_gcode);
+                                ui.close_menu();
+                            }
+                            if ui.button("HPGL").clicked() {
+                                self.export_plot("glyph.hpgl", to_hpgl);
+                                ui.close_menu();
+                            }
+                        });
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            ui.label("Width");
+                            ui.add(
+                                egui::DragValue::new(&mut self.cut.width)
+                                    .clamp_range(10.0..=2000.0)
+                                    .suffix(" mm"),
+                            );
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Edges");
+                            ui.add(
+                                egui::DragValue::new(&mut self.cut.edge_width)
+                                    .speed(0.05)
+                                    .clamp_range(0.1..=20.0)
+                                    .suffix(" mm"),
+                            );
+                            ui.label("Kerf");
+                            ui.add(
+                                egui::DragValue::new(&mut self.cut.kerf)
+                                    .speed(0.01)
+                                    .clamp_range(0.0..=5.0)
+                                    .suffix(" mm"),
+                            );
+                        });
+                        if ui.button("DXF").clicked() {
+                            self.export_dxf();
+                            ui.close_menu();
+                        }
+                        ui.horizontal(|ui| {
+                            ui.label("Bridges");
+                            ui.add(
+                                egui::DragValue::new(&mut self.cut.bridge_width)
+                                    .speed(0.05)
+                                    .clamp_range(0.1..=20.0)
+                                    .suffix(" mm"),
+                            );
+                            ui.add(egui::DragValue::new(&mut self.cut.bridges).clamp_range(1..=8))
+                                .on_hover_text("Bridges for every island");
+                        });
+                        ui.horizontal(|ui| {
+                            if ui.button("Stencil SVG").clicked() {
+                                self.export_stencil("stencil.svg", "image/svg+xml", to_stencil_svg);
+                                ui.close_menu();
+                            }
+                            if ui.button("Stencil DXF").clicked() {
+                                self.export_stencil("stencil.dxf", "image/vnd.dxf", to_stencil_dxf);
+                                ui.close_menu();
+                            }
+                        });
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            ui.label("Width");
+                            ui.add(
+                                egui::DragValue::new(&mut self.solid.width)
+                                    .clamp_range(10.0..=500.0)
+                                    .suffix(" mm"),
+                            );
+                            ui.label("Height");
+                            ui.add(
+                                egui::DragValue::new(&mut self.solid.height)
+                                    .speed(0.05)
+                                    .clamp_range(0.2..=20.0)
+                                    .suffix(" mm"),
+                            );
+                        });
+                        ui.horizontal(|ui| {
+                            egui::ComboBox::from_label("Base")
+                                .selected_text(self.solid.base.name())
+                                .show_ui(ui, |ui| {
+                                    BaseShape::ALL.into_iter().for_each(|base| {
+                                        ui.selectable_value(
+                                            &mut self.solid.base,
+                                            base,
+                                            base.name(),
+                                        );
+                                    });
+                                });
+                            ui.add_enabled(
+                                self.solid.base != BaseShape::None,
+                                egui::DragValue::new(&mut self.solid.base_height)
+                                    .speed(0.05)
+                                    .clamp_range(0.2..=20.0)
+                                    .suffix(" mm"),
+                            );
+                        });
+                        ui.horizontal(|ui| {
+                            if ui.button("STL").clicked() {
+                                self.export_solid("glyph.stl", "model/stl", to_stl);
+                                ui.close_menu();
+                            }
+                            if ui.button("OBJ").clicked() {
+                                self.export_solid("glyph.obj", "model/obj", |scene, options| {
+                                    to_obj(scene, options).map(String::into_bytes)
+                                });
+                                ui.close_menu();
+                            }
+                        });
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            ui.label("Graph");
+                            if ui.button("DOT").clicked() {
+                                self.export_graph("glyph.dot", "text/vnd.graphviz", to_dot);
+                                ui.close_menu();
+                            }
+                            if ui.button("GraphML").clicked() {
+                                self.export_graph(
+                                    "glyph.graphml",
+                                    "application/graphml+xml",
+                                    to_graphml,
+                                );
+                                ui.close_menu();
+                            }
+                            if ui.button("JSON").clicked() {
+                                self.export_graph("glyph.json", "application/json", to_graph_json);
+                                ui.close_menu();
+                            }
+                        });
+                    });
+                });
+                if !self.status.is_empty() {
+                    ui.colored_label(ui.visuals().error_fg_color, &self.status);
+                }
             });
+            if let Some(puzzle) = &mut self.puzzle {
+                ui.separator();
+                puzzle.ui(ui, &self.glyph, &self.rules);
+            }
         });
 
-        egui::CentralPanel::default().show(ctx, |ui| {
+        //List everything that is wrong with a connection table that failed to load
+        if !self.rules_errors.is_empty() {
+            let mut open = true;
+            egui::Window::new("Connection table errors")
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    self.rules_errors.iter().for_each(|error| {
+                        ui.label(error);
+                    });
+                });
+            if !open {
+                self.rules_errors.clear();
+            }
+        }
+
+        if self.graph_show && self.color_scheme != ColorScheme::Plain {
+            egui::SidePanel::right("legend").show(ctx, |ui| self.legend_ui(ui));
+        }
+
+        let mut theme_open = self.theme_open;
+        egui::Window::new("Theme")
+            .open(&mut theme_open)
+            .show(ctx, |ui| self.theme_ui(ctx, ui));
+        self.theme_open = theme_open;
+        //Switch the app between light and dark along with the theme
+        if ctx.style().visuals.dark_mode != self.theme.dark {
+            ctx.set_style(theme::themed_style(&self.theme));
+        }
+
+        let canvas =
+            egui::Frame::central_panel(&ctx.style()).fill(theme::color32(self.theme.background));
+        egui::CentralPanel::default().frame(canvas).show(ctx, |ui| {
             if self.graph_show {
+                let glyph_style = self.glyph_style();
+                edge::set_edge_looks(ui.ctx(), &self.glyph, &glyph_style);
+                node::set_node_looks(ui.ctx(), &self.glyph, &glyph_style);
+                edge::set_edge_marks(ui.ctx(), self.edge_marks);
+                if self.edge_marks.flow {
+                    ui.ctx().request_repaint();
+                }
+                if let Some((i, center, rotation)) =
+                    circle_layout::circle_handles(&self.glyph.circles, &glyph_style, ui)
+                {
+                    self.place_circle(i, center, rotation);
+                }
                 let graph = ui.add(
                     &mut GraphView::<_, _, _, _, NodeShape, EdgeShape>::new(&mut self.g)
                         .with_navigations(
@@ -875,8 +1447,16 @@ impl App for Lsegui {
                 );
                 let clip_rect = graph.rect;
                 ui.set_clip_rect(clip_rect);
-                self.circles.fix_circle_layout(&mut self.g);
-                self.circles.draw_circles(ui);
+                circle_layout::draw_circles(&self.glyph.circles, &glyph_style, ui);
+                let (dragging, released) =
+                    ui.input(|i| (i.pointer.any_down(), i.pointer.any_released()));
+                if self.on_ring && (dragging || released) {
+                    self.keep_nodes_on_ring();
+                }
+                //A drag has finished, so it can be undone
+                if released {
+                    self.checkpoint();
+                }
             }
         });
 
@@ -907,7 +1487,7 @@ fn main() {
     eframe::run_native(
         "Lost Skies Gall Language Art Generator",
         native_options,
-        Box::new(|cc| Box::new(Lsegui::new(cc))),
+        Box::new(|cc| Box::new(Lsegui::new(cc, None))),
     )
     .unwrap();
 }
@@ -920,12 +1500,17 @@ fn main() {
 
     let web_options = eframe::WebOptions::default();
 
-    wasm_bindgen_futures::spawn_local(async {
+    //A share link carries its glyph in the fragment
+    let shared = web_sys::window()
+        .and_then(|window| window.location().hash().ok())
+        .and_then(|fragment| SharedGlyph::from_fragment(&fragment));
+
+    wasm_bindgen_futures::spawn_local(async move {
         eframe::WebRunner::new()
             .start(
                 "the_canvas_id", // hardcode it
                 web_options,
-                Box::new(|cc| Box::new(Lsegui::new(cc))),
+                Box::new(|cc| Box::new(Lsegui::new(cc, shared))),
             )
             .await
             .expect("failed to start eframe");
@@ -1,9 +1,60 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
 use egui::{
     epaint::{CircleShape, TextShape},
-    FontFamily, FontId, Pos2, Shape, Stroke, Vec2,
+    Color32, FontFamily, FontId, Pos2, Shape, Stroke, Vec2,
 };
 use egui_graphs::{DisplayNode, DrawContext, NodeProps};
-use petgraph::{stable_graph::IndexType, EdgeType};
+use lsegui_core::{Glyph, GlyphStyle, LabelFont};
+use petgraph::{
+    stable_graph::{IndexType, NodeIndex},
+    EdgeType,
+};
+
+use crate::theme;
+
+//Where the looks of the nodes are kept in egui's memory
+const NODE_LOOKS: &str = "node looks";
+
+//The colour of every node by its index, and the sizes of all of them
+struct NodeLooks {
+    colors: HashMap<usize, Color32>,
+    radius: f32,
+    label_size: f32,
+    label_font: FontFamily,
+}
+
+//Hand the look of every node to the node shapes
+pub fn set_node_looks(ctx: &egui::Context, glyph: &Glyph, style: &GlyphStyle) {
+    let colors = glyph
+        .circles
+        .circle_list
+        .iter()
+        .enumerate()
+        .flat_map(|(word, circle)| {
+            circle
+                .word
+                .word
+                .chars()
+                .zip(circle.word.nodes.iter())
+                .map(move |(letter, node)| {
+                    let color = theme::color32(style.node_color_of(word, letter));
+                    (node.index(), color)
+                })
+        })
+        .collect::<HashMap<_, _>>();
+    let looks = NodeLooks {
+        colors,
+        radius: style.node_radius,
+        label_size: style.label_size,
+        label_font: match style.label_font {
+            LabelFont::Monospace => FontFamily::Monospace,
+            LabelFont::Proportional => FontFamily::Proportional,
+        },
+    };
+    ctx.data_mut(|data| data.insert_temp(egui::Id::new(NODE_LOOKS), Arc::new(looks)));
+}
 
 /// This is the default node shape which is used to display nodes in the graph.
 ///
@@ -12,6 +63,8 @@ use petgraph::{stable_graph::IndexType, EdgeType};
 #[derive(Clone, Debug)]
 pub struct NodeShape {
     pub pos: Pos2,
+    //The node of the glyph this shape shows, which its colour is looked up by
+    pub node: usize,
 
     pub selected: bool,
     pub dragged: bool,
@@ -22,10 +75,11 @@ pub struct NodeShape {
     pub radius: f32,
 }
 
-impl<N: Clone> From<NodeProps<N>> for NodeShape {
-    fn from(node_props: NodeProps<N>) -> Self {
+impl From<NodeProps<NodeIndex>> for NodeShape {
+    fn from(node_props: NodeProps<NodeIndex>) -> Self {
         NodeShape {
             pos: node_props.location,
+            node: node_props.payload.index(),
             selected: node_props.selected,
             dragged: node_props.dragged,
             label_text: node_props.label.to_string(),
@@ -54,7 +108,24 @@ impl<N: Clone, E: Clone, Ty: EdgeType, Ix: IndexType> DisplayNode<N, E, Ty, Ix>
             false => ctx.ctx.style().visuals.widgets.inactive,
         };
 
-        let color = style.fg_stroke.color;
+        let looks = ctx
+            .ctx
+            .data(|data| data.get_temp::<Arc<NodeLooks>>(egui::Id::new(NODE_LOOKS)));
+        //Kept on the shape so hit tests match the drawn size
+        if let Some(looks) = &looks {
+            self.radius = looks.radius;
+        }
+        let color = match (&looks, is_interacted) {
+            (Some(looks), false) => looks.colors.get(&self.node).copied(),
+            _ => None,
+        }
+        .unwrap_or(style.fg_stroke.color);
+        let (label_size, label_font) = looks
+            .as_ref()
+            .map_or((self.radius, FontFamily::Monospace), |looks| {
+                (looks.label_size, looks.label_font.clone())
+            });
+        let label_size = ctx.meta.canvas_to_screen_size(label_size);
 
         let circle_center = ctx.meta.canvas_to_screen_pos(self.pos);
         let circle_radius = ctx.meta.canvas_to_screen_size(self.radius);
@@ -70,7 +141,7 @@ impl<N: Clone, E: Clone, Ty: EdgeType, Ix: IndexType> DisplayNode<N, E, Ty, Ix>
         let galley = ctx.ctx.fonts(|f| {
             f.layout_no_wrap(
                 self.label_text.clone(),
-                FontId::new(circle_radius, FontFamily::Monospace),
+                FontId::new(label_size, label_font),
                 color,
             )
         });
@@ -78,7 +149,7 @@ impl<N: Clone, E: Clone, Ty: EdgeType, Ix: IndexType> DisplayNode<N, E, Ty, Ix>
         // display label centered over the circle
         let label_pos = Pos2::new(
             circle_center.x - galley.size().x / 2.,
-            circle_center.y - circle_radius * 2.,
+            circle_center.y - circle_radius - label_size,
         );
 
         let label_shape = TextShape::new(label_pos, galley);
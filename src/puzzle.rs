@@ -0,0 +1,104 @@
+use eframe::egui;
+
+use lsegui_core::{decode, reads_as, ConnectionTable, Glyph, Phrase, Word};
+
+//Readings listed in a hint before the rest are summed up
+const HINT_READINGS: usize = 3;
+
+//Guess the phrase of a glyph with its letters hidden
+#[derive(Default)]
+pub struct Puzzle {
+    guess: String,
+    //What the last check or hint found
+    message: String,
+}
+
+impl Puzzle {
+    pub fn ui(&mut self, ui: &mut egui::Ui, glyph: &Glyph, rules: &ConnectionTable) {
+        ui.horizontal(|ui| {
+            ui.label("Guess the phrase:");
+            let re = ui.text_edit_singleline(&mut self.guess);
+            if ui.button("Check").clicked()
+                || (re.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)))
+            {
+                self.check(glyph, rules);
+            }
+            if ui.button("Hint").clicked() {
+                self.hint(glyph, rules);
+            }
+            ui.label(&self.message);
+        });
+    }
+
+    //Count the guessed words that give the same connections as the glyph
+    fn check(&mut self, glyph: &Glyph, rules: &ConnectionTable) {
+        let Ok(guess) = Phrase::new(&self.guess) else {
+            self.message = String::from("Type a guess first");
+            return;
+        };
+        let words = &glyph.phrase.phrase_words;
+        let right = right_words(glyph, rules, &guess.phrase_words);
+
+        self.message = if guess.phrase_words.len() != words.len() {
+            format!("The glyph has {} words", words.len())
+        } else if right.iter().all(|right| *right) {
+            String::from("Correct!")
+        } else {
+            format!(
+                "{} of {} words read right",
+                right.iter().filter(|right| **right).count(),
+                words.len()
+            )
+        };
+    }
+
+    //Show the likeliest readings of the first word that isn't guessed yet
+    fn hint(&mut self, glyph: &Glyph, rules: &ConnectionTable) {
+        //Nothing is guessed yet when the guess has no letters
+        let guess = Phrase::new(&self.guess)
+            .map(|guess| guess.phrase_words)
+            .unwrap_or_default();
+        let right = right_words(glyph, rules, &guess);
+        let Some(i) = right.iter().position(|right| !right) else {
+            self.message = String::from("Every word already reads right");
+            return;
+        };
+
+        let word = &glyph.phrase.phrase_words[i];
+        let decoded = decode(glyph.graph(), &[word.nodes.clone()], rules).remove(0);
+        let readings = decoded
+            .readings
+            .iter()
+            .take(HINT_READINGS)
+            .map(|(reading, _)| reading.as_str())
+            .collect::<Vec<_>>();
+        let more = decoded.readings.len().saturating_sub(HINT_READINGS);
+
+        self.message = match (readings.is_empty(), more, decoded.truncated) {
+            (true, _, _) => format!("Word {} can't be read with these rules", i + 1),
+            (false, 0, false) => format!("Word {} could be {}", i + 1, readings.join(", ")),
+            (false, _, truncated) => format!(
+                "Word {} could be {} or {}{} others",
+                i + 1,
+                readings.join(", "),
+                if truncated { "over " } else { "" },
+                more
+            ),
+        };
+    }
+}
+
+//Which words of the glyph the guess reads right, in order
+fn right_words(glyph: &Glyph, rules: &ConnectionTable, guess: &[Word]) -> Vec<bool> {
+    glyph
+        .phrase
+        .phrase_words
+        .iter()
+        .enumerate()
+        .map(|(i, word)| {
+            guess
+                .get(i)
+                .is_some_and(|guessed| reads_as(&guessed.word, glyph.graph(), &word.nodes, rules))
+        })
+        .collect()
+}
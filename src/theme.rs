@@ -5,6 +5,7 @@ use egui::{
     style::{Interaction, Margin, Selection, Spacing, WidgetVisuals, Widgets},
     Color32, Rounding, Stroke, Style, Vec2, Visuals,
 };
+use lsegui_core::{Color, Theme};
 
 pub fn style() -> Style {
     Style {
@@ -215,3 +216,26 @@ pub fn style() -> Style {
         ..Default::default()
     }
 }
+
+//The style above for dark themes, with egui's light visuals for light ones
+pub fn themed_style(theme: &Theme) -> Style {
+    let mut style = style();
+    if !theme.dark {
+        style.visuals = Visuals::light();
+    }
+    style
+}
+
+//A colour button that keeps the alpha of the colour
+pub fn color_edit(ui: &mut egui::Ui, color: &mut Color) -> egui::Response {
+    let mut rgb = [color.r, color.g, color.b];
+    let response = ui.color_edit_button_srgb(&mut rgb);
+    if response.changed() {
+        *color = Color::rgba(rgb[0], rgb[1], rgb[2], color.a);
+    }
+    response
+}
+
+pub fn color32(color: Color) -> Color32 {
+    Color32::from_rgba_unmultiplied(color.r, color.g, color.b, color.a)
+}